@@ -0,0 +1,346 @@
+use super::Result;
+use std::path::{Path, PathBuf};
+
+/// A cgroup hierarchy a process is a member of, as listed in `/proc/<pid>/cgroup`.
+#[derive(Debug, PartialEq)]
+pub struct Cgroup {
+    pub hierarchy_id: u64,
+    /// The controllers bound to the hierarchy, empty for the cgroup v2 unified hierarchy.
+    pub controllers: Vec<String>,
+    /// Path of the cgroup relative to the mount point of the hierarchy.
+    pub path: String,
+}
+
+/// All cgroup hierarchies a process is a member of.
+#[derive(Debug, PartialEq)]
+pub struct Cgroups {
+    pub cgroups: Vec<Cgroup>,
+    /// Whether these are the cgroups of this process. Only our own cgroup is mounted as the root
+    /// of the hierarchy when running in a container without a cgroup namespace.
+    pub(crate) own_process: bool,
+}
+
+impl Cgroups {
+    /// Path of the process in the cgroup v2 unified hierarchy.
+    pub fn unified_path(&self) -> Option<&str> {
+        self.cgroups
+            .iter()
+            .find(|cgroup| cgroup.hierarchy_id == 0 && cgroup.controllers.is_empty())
+            .map(|cgroup| cgroup.path.as_str())
+    }
+
+    /// Path of the process in the cgroup v1 hierarchy the given controller is bound to.
+    pub fn controller_path(&self, controller: &str) -> Option<&str> {
+        self.cgroups
+            .iter()
+            .find(|cgroup| cgroup.controllers.iter().any(|c| c == controller))
+            .map(|cgroup| cgroup.path.as_str())
+    }
+
+    /// Directory of the cgroup of the process in the cgroup v2 unified hierarchy.
+    #[cfg(target_os = "linux")]
+    pub fn unified_dir(&self) -> Option<PathBuf> {
+        self.unified_dir_in(Path::new(SYS_FS_CGROUP_DIR))
+    }
+
+    /// Directory of the cgroup of the process in the cgroup v1 hierarchy the given controller is
+    /// bound to.
+    #[cfg(target_os = "linux")]
+    pub fn controller_dir(&self, controller: &str) -> Option<PathBuf> {
+        self.controller_dir_in(Path::new(SYS_FS_CGROUP_DIR), controller)
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn unified_dir_in(&self, cgroup_dir: &Path) -> Option<PathBuf> {
+        self.unified_path()
+            .map(|path| os::resolve_dir(&os::unified_mount_dir(cgroup_dir), path, self.own_process))
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn controller_dir_in(&self, cgroup_dir: &Path, controller: &str) -> Option<PathBuf> {
+        self.controller_path(controller)
+            .map(|path| os::resolve_dir(&cgroup_dir.join(controller), path, self.own_process))
+    }
+}
+
+/// Mount point of the cgroup hierarchies.
+#[cfg(target_os = "linux")]
+pub(crate) const SYS_FS_CGROUP_DIR: &str = "/sys/fs/cgroup";
+
+/// Read the cgroups of this process.
+#[cfg(target_os = "linux")]
+pub fn read() -> Result<Cgroups> {
+    os::read()
+}
+
+/// Read the cgroups of a process with given pid.
+#[cfg(target_os = "linux")]
+pub fn read_of(pid: libc::pid_t) -> Result<Cgroups> {
+    os::read_of(pid)
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) mod os {
+    use super::super::{dir_exists, file_to_buf_reader, parse_u64, path_to_string, Result};
+    use super::{Cgroup, Cgroups};
+    use crate::error::ProbeError;
+    use std::io::BufRead;
+    use std::path::{Path, PathBuf};
+
+    #[inline]
+    pub fn read() -> Result<Cgroups> {
+        let cgroups = read_and_parse_proc_cgroup(Path::new("/proc/self/cgroup"))?;
+        Ok(Cgroups {
+            own_process: true,
+            ..cgroups
+        })
+    }
+
+    #[inline]
+    pub fn read_of(pid: libc::pid_t) -> Result<Cgroups> {
+        read_and_parse_proc_cgroup(Path::new(&format!("/proc/{}/cgroup", pid)))
+    }
+
+    pub fn read_and_parse_proc_cgroup(path: &Path) -> Result<Cgroups> {
+        let reader = file_to_buf_reader(path)?;
+
+        let mut cgroups = Vec::new();
+        for line_result in reader.lines() {
+            let line = line_result.map_err(|e| ProbeError::IO(e, path_to_string(path)))?;
+            if line.is_empty() {
+                continue;
+            }
+
+            // columns: hierarchy-ID:controller-list:cgroup-path
+            let segments: Vec<&str> = line.splitn(3, ':').collect();
+            if segments.len() != 3 {
+                return Err(ProbeError::UnexpectedContent(
+                    "Incorrect number of segments".to_owned(),
+                ));
+            }
+
+            cgroups.push(Cgroup {
+                hierarchy_id: parse_u64(segments[0])?,
+                controllers: segments[1]
+                    .split(',')
+                    .filter(|controller| !controller.is_empty())
+                    .map(|controller| controller.to_owned())
+                    .collect(),
+                path: segments[2].to_owned(),
+            });
+        }
+
+        Ok(Cgroups {
+            cgroups,
+            own_process: false,
+        })
+    }
+
+    /// On hybrid systems the unified hierarchy is mounted next to the v1 hierarchies.
+    pub fn unified_mount_dir(cgroup_dir: &Path) -> PathBuf {
+        let hybrid_dir = cgroup_dir.join("unified");
+        if dir_exists(&hybrid_dir) {
+            hybrid_dir
+        } else {
+            cgroup_dir.to_path_buf()
+        }
+    }
+
+    /// Find the directory of a cgroup path within the mounted hierarchy. When a container is
+    /// started without a cgroup namespace the path is the one from the host, while the
+    /// container's own cgroup is mounted as the root of the hierarchy. In that case the path
+    /// does not exist within the mount and, for our own process, the root of the mount is used.
+    /// For other processes the missing directory is returned, reading from it results in an
+    /// error instead of the stats of another cgroup.
+    pub fn resolve_dir(mount_dir: &Path, cgroup_path: &str, own_process: bool) -> PathBuf {
+        let dir = mount_dir.join(cgroup_path.trim_start_matches('/'));
+        if own_process && !dir_exists(&dir) {
+            mount_dir.to_path_buf()
+        } else {
+            dir
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+    use super::super::ProbeError;
+    use super::os::{read_and_parse_proc_cgroup, resolve_dir, unified_mount_dir};
+    use super::{Cgroup, Cgroups};
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_read_cgroups() {
+        assert!(super::read().is_ok());
+        assert!(!super::read().unwrap().cgroups.is_empty());
+    }
+
+    #[test]
+    fn test_read_cgroups_of() {
+        let pid = unsafe { libc::getpid() };
+        assert_eq!(
+            super::read_of(pid).unwrap().cgroups,
+            super::read().unwrap().cgroups
+        );
+    }
+
+    #[test]
+    fn test_read_cgroups_of_invalid_pid() {
+        assert!(super::read_of(0).is_err());
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_cgroup_docker() {
+        let path = Path::new("fixtures/linux/proc/self/cgroup/docker");
+        let cgroups = read_and_parse_proc_cgroup(path).unwrap();
+        let container_path =
+            "/docker/0c703b75cdeaad7c933aa68b4678cc5c37a12d5ef5d7cb52c9cefe684d98e575";
+
+        assert_eq!(14, cgroups.cgroups.len());
+        assert_eq!(
+            Cgroup {
+                hierarchy_id: 14,
+                controllers: vec!["name=systemd".to_owned()],
+                path: container_path.to_owned(),
+            },
+            cgroups.cgroups[0]
+        );
+        assert_eq!(Some(container_path), cgroups.controller_path("memory"));
+        assert_eq!(Some(container_path), cgroups.controller_path("cpuacct"));
+        assert_eq!(Some("/docker"), cgroups.controller_path("name=openrc"));
+        assert_eq!(None, cgroups.unified_path());
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_cgroup_combined_controllers() {
+        let path = Path::new("fixtures/linux/proc/self/cgroup/kubernetes");
+        let cgroups = read_and_parse_proc_cgroup(path).unwrap();
+        let container_path = "/kubepods/besteffort/pod75f47a71-6279-11e8-aeff-08002750f0f7/f69dc40a90102b2897bb0d4a010fa9adf88f20f58b9707f643cc84e510c28e57";
+
+        assert_eq!(
+            vec!["cpu".to_owned(), "cpuacct".to_owned()],
+            cgroups.cgroups[6].controllers
+        );
+        assert_eq!(Some(container_path), cgroups.controller_path("cpu"));
+        assert_eq!(Some(container_path), cgroups.controller_path("cpuacct"));
+        assert_eq!(None, cgroups.controller_path("cpu,cpuacct"));
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_cgroup_v2() {
+        let path = Path::new("fixtures/linux/proc/self/cgroup/v2");
+        let cgroups = read_and_parse_proc_cgroup(path).unwrap();
+
+        let expected = Cgroups {
+            cgroups: vec![Cgroup {
+                hierarchy_id: 0,
+                controllers: vec![],
+                path: "/system.slice/app.service".to_owned(),
+            }],
+            own_process: false,
+        };
+        assert_eq!(expected, cgroups);
+        assert_eq!(Some("/system.slice/app.service"), cgroups.unified_path());
+        assert_eq!(None, cgroups.controller_path("memory"));
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_cgroup_hybrid() {
+        let path = Path::new("fixtures/linux/proc/self/cgroup/hybrid");
+        let cgroups = read_and_parse_proc_cgroup(path).unwrap();
+
+        assert_eq!(Some("/user.slice"), cgroups.unified_path());
+        assert_eq!(Some("/user.slice"), cgroups.controller_path("memory"));
+        assert_eq!(Some("/"), cgroups.controller_path("cpu"));
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_cgroup_wrong_path() {
+        match read_and_parse_proc_cgroup(Path::new("/nonsense")) {
+            Err(ProbeError::IO(_, _)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_cgroup_garbage() {
+        let path = Path::new("fixtures/linux/proc/self/cgroup/garbage");
+        match read_and_parse_proc_cgroup(path) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_resolve_dir() {
+        let mount_dir = Path::new("fixtures/linux/sys/fs/cgroup_v2");
+
+        assert_eq!(
+            PathBuf::from("fixtures/linux/sys/fs/cgroup_v2/memory"),
+            resolve_dir(mount_dir, "/memory", true)
+        );
+        assert_eq!(
+            PathBuf::from("fixtures/linux/sys/fs/cgroup_v2"),
+            resolve_dir(mount_dir, "/", true)
+        );
+        // Falls back to the root of the mount when the path is from another cgroup namespace
+        assert_eq!(
+            PathBuf::from("fixtures/linux/sys/fs/cgroup_v2"),
+            resolve_dir(mount_dir, "/docker/0c703b75cdea", true)
+        );
+    }
+
+    #[test]
+    fn test_resolve_dir_other_process() {
+        let mount_dir = Path::new("fixtures/linux/sys/fs/cgroup_v2");
+
+        assert_eq!(
+            PathBuf::from("fixtures/linux/sys/fs/cgroup_v2/memory"),
+            resolve_dir(mount_dir, "/memory", false)
+        );
+        // The cgroup is out of view or was removed, this must not resolve to the root
+        assert_eq!(
+            PathBuf::from("fixtures/linux/sys/fs/cgroup_v2/docker/0c703b75cdea"),
+            resolve_dir(mount_dir, "/docker/0c703b75cdea", false)
+        );
+    }
+
+    #[test]
+    fn test_unified_mount_dir() {
+        assert_eq!(
+            PathBuf::from("fixtures/linux/sys/fs/cgroup_v2"),
+            unified_mount_dir(Path::new("fixtures/linux/sys/fs/cgroup_v2"))
+        );
+        assert_eq!(
+            PathBuf::from("fixtures/linux/sys/fs/cgroup_hybrid/unified"),
+            unified_mount_dir(Path::new("fixtures/linux/sys/fs/cgroup_hybrid"))
+        );
+    }
+
+    #[test]
+    fn test_dirs_hybrid() {
+        let cgroups =
+            read_and_parse_proc_cgroup(Path::new("fixtures/linux/proc/self/cgroup/hybrid"))
+                .unwrap();
+        let cgroup_dir = Path::new("fixtures/linux/sys/fs/cgroup_hybrid");
+
+        assert_eq!(
+            Some(PathBuf::from(
+                "fixtures/linux/sys/fs/cgroup_hybrid/unified/user.slice"
+            )),
+            cgroups.unified_dir_in(cgroup_dir)
+        );
+        assert_eq!(
+            Some(PathBuf::from(
+                "fixtures/linux/sys/fs/cgroup_hybrid/memory/user.slice"
+            )),
+            cgroups.controller_dir_in(cgroup_dir, "memory")
+        );
+        assert_eq!(
+            Some(PathBuf::from("fixtures/linux/sys/fs/cgroup_hybrid/cpuacct")),
+            cgroups.controller_dir_in(cgroup_dir, "cpuacct")
+        );
+        assert_eq!(None, cgroups.controller_dir_in(cgroup_dir, "blkio"));
+    }
+}
@@ -0,0 +1,357 @@
+use super::Result;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ContainerRuntime {
+    Docker,
+    Containerd,
+    CriO,
+    Podman,
+    Lxc,
+}
+
+/// Kubernetes quality of service class of a pod
+#[derive(Debug, PartialEq)]
+pub enum QosClass {
+    BestEffort,
+    Burstable,
+    Guaranteed,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct KubernetesPod {
+    pub uid: String,
+    pub qos_class: QosClass,
+}
+
+/// The container a process runs in
+#[derive(Debug, PartialEq)]
+pub struct Container {
+    /// The runtime, when it can be derived from the cgroup path.
+    pub runtime: Option<ContainerRuntime>,
+    pub id: Option<String>,
+    /// Only present when the container is part of a Kubernetes pod.
+    pub kubernetes: Option<KubernetesPod>,
+}
+
+/// Read the container this process runs in, `None` when not running in a container.
+#[cfg(target_os = "linux")]
+pub fn read() -> Result<Option<Container>> {
+    os::read()
+}
+
+/// Read the container a process with given pid runs in, `None` when not running in a container.
+#[cfg(target_os = "linux")]
+pub fn read_of(pid: libc::pid_t) -> Result<Option<Container>> {
+    os::read_of(pid)
+}
+
+/// Check if this process runs in a container.
+#[cfg(target_os = "linux")]
+pub fn in_container() -> Result<bool> {
+    os::read().map(|container| container.is_some())
+}
+
+#[cfg(target_os = "linux")]
+mod os {
+    use super::super::cgroup::{self, Cgroups};
+    use super::super::Result;
+    use super::{Container, ContainerRuntime, KubernetesPod, QosClass};
+    use std::path::Path;
+
+    // Prefixes of the systemd scopes container runtimes create for their containers.
+    const SCOPE_PREFIXES: [(&str, ContainerRuntime); 4] = [
+        ("docker-", ContainerRuntime::Docker),
+        ("cri-containerd-", ContainerRuntime::Containerd),
+        ("crio-", ContainerRuntime::CriO),
+        ("libpod-", ContainerRuntime::Podman),
+    ];
+
+    #[inline]
+    pub fn read() -> Result<Option<Container>> {
+        Ok(detect_container(&cgroup::read()?, Path::new("/")))
+    }
+
+    #[inline]
+    pub fn read_of(pid: libc::pid_t) -> Result<Option<Container>> {
+        let root = format!("/proc/{}/root", pid);
+        Ok(detect_container(&cgroup::read_of(pid)?, Path::new(&root)))
+    }
+
+    /// Detect the container based on the cgroup paths of a process. With a private cgroup
+    /// namespace the paths don't reveal the container, in that case fall back to the marker
+    /// files Docker and Podman create in the root of the container.
+    pub fn detect_container(cgroups: &Cgroups, root: &Path) -> Option<Container> {
+        let mut detected: Option<Container> = None;
+        for cgroup in cgroups.cgroups.iter() {
+            if let Some(container) = parse_cgroup_path(&cgroup.path) {
+                // Some hierarchies, like `name=openrc`, only contain part of the path.
+                if container.id.is_some() {
+                    return Some(container);
+                }
+                detected.get_or_insert(container);
+            }
+        }
+        if detected.is_some() {
+            return detected;
+        }
+
+        if root.join(".dockerenv").exists() {
+            return Some(Container {
+                runtime: Some(ContainerRuntime::Docker),
+                id: None,
+                kubernetes: None,
+            });
+        }
+        if root.join("run/.containerenv").exists() {
+            return Some(Container {
+                runtime: Some(ContainerRuntime::Podman),
+                id: None,
+                kubernetes: None,
+            });
+        }
+        None
+    }
+
+    pub fn parse_cgroup_path(path: &str) -> Option<Container> {
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+        if segments.iter().any(|s| s.starts_with("kubepods")) {
+            return parse_kubernetes_segments(&segments);
+        }
+
+        for (index, segment) in segments.iter().enumerate() {
+            let runtime = match *segment {
+                "docker" => Some(ContainerRuntime::Docker),
+                "lxc" => Some(ContainerRuntime::Lxc),
+                _ => None,
+            };
+            if runtime.is_some() {
+                return Some(Container {
+                    runtime,
+                    id: segments.get(index + 1).map(|id| id.to_string()),
+                    kubernetes: None,
+                });
+            }
+            if let Some(name) = segment.strip_prefix("lxc.payload.") {
+                return Some(Container {
+                    runtime: Some(ContainerRuntime::Lxc),
+                    id: Some(name.to_owned()),
+                    kubernetes: None,
+                });
+            }
+            if let Some((runtime, id)) = parse_scope(segment) {
+                return Some(Container {
+                    runtime: Some(runtime),
+                    id: Some(id.to_owned()),
+                    kubernetes: None,
+                });
+            }
+        }
+
+        None
+    }
+
+    // Paths look like `/kubepods/<qos>/pod<uid>/<id>` with the cgroupfs driver and like
+    // `/kubepods.slice/kubepods-<qos>.slice/kubepods-<qos>-pod<uid>.slice/<runtime>-<id>.scope`
+    // with the systemd driver. Guaranteed pods don't have the qos segment.
+    fn parse_kubernetes_segments(segments: &[&str]) -> Option<Container> {
+        let pod_index = segments.iter().position(|s| parse_pod_uid(s).is_some())?;
+        let uid = parse_pod_uid(segments[pod_index])?;
+
+        let parents = &segments[..pod_index];
+        let qos_class = if parents.iter().any(|s| s.contains("besteffort")) {
+            QosClass::BestEffort
+        } else if parents.iter().any(|s| s.contains("burstable")) {
+            QosClass::Burstable
+        } else {
+            QosClass::Guaranteed
+        };
+
+        let (runtime, id) = match segments.get(pod_index + 1) {
+            Some(segment) => match parse_scope(segment) {
+                Some((runtime, id)) => (Some(runtime), Some(id.to_owned())),
+                None => (None, Some(segment.to_string())),
+            },
+            None => (None, None),
+        };
+
+        Some(Container {
+            runtime,
+            id,
+            kubernetes: Some(KubernetesPod { uid, qos_class }),
+        })
+    }
+
+    fn parse_pod_uid(segment: &str) -> Option<String> {
+        if let Some(uid) = segment.strip_prefix("pod") {
+            return Some(uid.to_owned());
+        }
+        // The systemd driver replaces the dashes in the uid with underscores.
+        let name = segment.strip_suffix(".slice")?;
+        let index = name.rfind("-pod")?;
+        Some(name[index + 4..].replace('_', "-"))
+    }
+
+    fn parse_scope(segment: &str) -> Option<(ContainerRuntime, &str)> {
+        let name = segment.strip_suffix(".scope")?;
+        SCOPE_PREFIXES
+            .iter()
+            .find_map(|(prefix, runtime)| name.strip_prefix(prefix).map(|id| (runtime.clone(), id)))
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+    use super::super::cgroup;
+    use super::os::{detect_container, parse_cgroup_path};
+    use super::{Container, ContainerRuntime, KubernetesPod, QosClass};
+    use std::path::Path;
+
+    fn detect_from_fixture(name: &str, root: &str) -> Option<Container> {
+        let path = format!("fixtures/linux/proc/self/cgroup/{}", name);
+        let cgroups = cgroup::os::read_and_parse_proc_cgroup(Path::new(&path)).unwrap();
+        detect_container(&cgroups, Path::new(root))
+    }
+
+    #[test]
+    fn test_read() {
+        assert!(super::read().is_ok());
+        assert_eq!(
+            super::in_container().unwrap(),
+            super::read().unwrap().is_some()
+        );
+    }
+
+    #[test]
+    fn test_read_of() {
+        let pid = unsafe { libc::getpid() };
+        assert_eq!(super::read_of(pid).unwrap(), super::read().unwrap());
+    }
+
+    #[test]
+    fn test_read_of_invalid_pid() {
+        assert!(super::read_of(0).is_err());
+    }
+
+    #[test]
+    fn test_detect_docker() {
+        let expected = Container {
+            runtime: Some(ContainerRuntime::Docker),
+            id: Some("0c703b75cdeaad7c933aa68b4678cc5c37a12d5ef5d7cb52c9cefe684d98e575".to_owned()),
+            kubernetes: None,
+        };
+        assert_eq!(Some(expected), detect_from_fixture("docker", "/nonsense"));
+    }
+
+    #[test]
+    fn test_detect_docker_systemd() {
+        let expected = Container {
+            runtime: Some(ContainerRuntime::Docker),
+            id: Some("09f1c4d420025670a3633edbc9b31450f1d6b2ff87b5912a10c320ad398c7215".to_owned()),
+            kubernetes: None,
+        };
+        assert_eq!(
+            Some(expected),
+            detect_from_fixture("docker_systemd", "/nonsense")
+        );
+    }
+
+    #[test]
+    fn test_detect_kubernetes() {
+        let expected = Container {
+            runtime: None,
+            id: Some("f69dc40a90102b2897bb0d4a010fa9adf88f20f58b9707f643cc84e510c28e57".to_owned()),
+            kubernetes: Some(KubernetesPod {
+                uid: "75f47a71-6279-11e8-aeff-08002750f0f7".to_owned(),
+                qos_class: QosClass::BestEffort,
+            }),
+        };
+        assert_eq!(
+            Some(expected),
+            detect_from_fixture("kubernetes", "/nonsense")
+        );
+    }
+
+    #[test]
+    fn test_detect_kubernetes_systemd() {
+        let expected = Container {
+            runtime: Some(ContainerRuntime::Containerd),
+            id: Some("4c2a5f0e8b8cc7f3a1d29e0e6d7f2b8e9a4b3c2d1e0f9a8b7c6d5e4f3a2b1c0d".to_owned()),
+            kubernetes: Some(KubernetesPod {
+                uid: "0a7f3b52-1c9d-4e8a-b6f1-2d3c4b5a6978".to_owned(),
+                qos_class: QosClass::Burstable,
+            }),
+        };
+        assert_eq!(
+            Some(expected),
+            detect_from_fixture("kubernetes_systemd", "/nonsense")
+        );
+    }
+
+    #[test]
+    fn test_detect_lxc() {
+        let expected = Container {
+            runtime: Some(ContainerRuntime::Lxc),
+            id: Some("1a2e485e-3947-4bb6-8c24-8774f0859648".to_owned()),
+            kubernetes: None,
+        };
+        assert_eq!(Some(expected), detect_from_fixture("lxc", "/nonsense"));
+    }
+
+    #[test]
+    fn test_detect_none() {
+        assert_eq!(None, detect_from_fixture("none", "/nonsense"));
+    }
+
+    #[test]
+    fn test_detect_dockerenv() {
+        let expected = Container {
+            runtime: Some(ContainerRuntime::Docker),
+            id: None,
+            kubernetes: None,
+        };
+        assert_eq!(
+            Some(expected),
+            detect_from_fixture("v2", "fixtures/linux/container/dockerenv")
+        );
+    }
+
+    #[test]
+    fn test_parse_cgroup_path_guaranteed_pod() {
+        let container = parse_cgroup_path(
+            "/kubepods.slice/kubepods-pod0a7f3b52_1c9d_4e8a_b6f1_2d3c4b5a6978.slice/crio-4c2a5f0e.scope",
+        )
+        .unwrap();
+
+        assert_eq!(Some(ContainerRuntime::CriO), container.runtime);
+        assert_eq!(Some("4c2a5f0e".to_owned()), container.id);
+        assert_eq!(
+            Some(KubernetesPod {
+                uid: "0a7f3b52-1c9d-4e8a-b6f1-2d3c4b5a6978".to_owned(),
+                qos_class: QosClass::Guaranteed,
+            }),
+            container.kubernetes
+        );
+    }
+
+    #[test]
+    fn test_parse_cgroup_path_podman() {
+        let expected = Container {
+            runtime: Some(ContainerRuntime::Podman),
+            id: Some("8d2c1b0a".to_owned()),
+            kubernetes: None,
+        };
+        assert_eq!(
+            Some(expected),
+            parse_cgroup_path("/machine.slice/libpod-8d2c1b0a.scope")
+        );
+    }
+
+    #[test]
+    fn test_parse_cgroup_path_host() {
+        assert_eq!(None, parse_cgroup_path("/"));
+        assert_eq!(None, parse_cgroup_path("/user.slice/user-1000.slice"));
+        assert_eq!(None, parse_cgroup_path("/system.slice/app.service"));
+    }
+}
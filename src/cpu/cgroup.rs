@@ -1,3 +1,4 @@
+use crate::cgroup::{self, Cgroups};
 use crate::error::ProbeError;
 use crate::{calculate_time_difference, dir_exists, time_adjusted, Result};
 use std::path::Path;
@@ -36,6 +37,12 @@ impl CgroupCpuMeasurement {
                 self.stat.system,
                 time_difference,
             )?,
+            throttling: match (&self.stat.throttling, &next_measurement.stat.throttling) {
+                (Some(throttling), Some(next_throttling)) => {
+                    Some(throttling.calculate_per_minute(next_throttling, time_difference)?)
+                }
+                _ => None,
+            },
         })
     }
 }
@@ -46,6 +53,56 @@ pub struct CgroupCpuStat {
     pub total_usage: u64,
     pub user: u64,
     pub system: u64,
+    /// Only present when the cpu controller reports throttling statistics.
+    pub throttling: Option<CgroupCpuThrottling>,
+}
+
+/// Container CPU throttling stats
+#[derive(Debug, PartialEq, Clone)]
+pub struct CgroupCpuThrottling {
+    /// Number of enforcement periods that have elapsed
+    pub nr_periods: u64,
+    /// Number of periods in which the cgroup was throttled
+    pub nr_throttled: u64,
+    /// Total time the cgroup was throttled in nanoseconds
+    pub throttled_time: u64,
+}
+
+impl CgroupCpuThrottling {
+    fn calculate_per_minute(
+        &self,
+        next_throttling: &CgroupCpuThrottling,
+        time_difference: u64,
+    ) -> Result<CgroupCpuThrottling> {
+        Ok(CgroupCpuThrottling {
+            nr_periods: time_adjusted(
+                "nr_periods",
+                next_throttling.nr_periods,
+                self.nr_periods,
+                time_difference,
+            )?,
+            nr_throttled: time_adjusted(
+                "nr_throttled",
+                next_throttling.nr_throttled,
+                self.nr_throttled,
+                time_difference,
+            )?,
+            throttled_time: time_adjusted(
+                "throttled_time",
+                next_throttling.throttled_time,
+                self.throttled_time,
+                time_difference,
+            )?,
+        })
+    }
+
+    /// Percentage of the enforcement periods in which the cgroup was throttled
+    pub fn throttled_percentage(&self) -> f32 {
+        if self.nr_periods == 0 {
+            return 0.0;
+        }
+        (self.nr_throttled as f64 / self.nr_periods as f64 * 100.0) as f32
+    }
 }
 
 impl CgroupCpuStat {
@@ -55,6 +112,10 @@ impl CgroupCpuStat {
             total_usage: self.percentage_of_total(self.total_usage),
             user: self.percentage_of_total(self.user),
             system: self.percentage_of_total(self.system),
+            throttled: self
+                .throttling
+                .as_ref()
+                .map(|throttling| throttling.throttled_percentage()),
         }
     }
 
@@ -66,6 +127,7 @@ impl CgroupCpuStat {
             total_usage: (self.total_usage as f64 / cpu_count).round() as u64,
             user: (self.user as f64 / cpu_count).round() as u64,
             system: (self.system as f64 / cpu_count).round() as u64,
+            throttling: self.throttling.clone(),
         }
     }
 
@@ -81,42 +143,73 @@ pub struct CgroupCpuStatPercentages {
     pub total_usage: f32,
     pub user: f32,
     pub system: f32,
+    /// Percentage of the enforcement periods in which the cgroup was throttled
+    pub throttled: Option<f32>,
 }
 
 /// Read the current CPU stats of the container.
 #[cfg(target_os = "linux")]
 pub fn read(cpu_count: Option<f64>) -> Result<CgroupCpuMeasurement> {
+    read_from_cgroups(&cgroup::read()?, cpu_count)
+}
+
+/// Read the current CPU stats of the cgroup of a process with given pid.
+#[cfg(target_os = "linux")]
+pub fn read_of(pid: libc::pid_t, cpu_count: Option<f64>) -> Result<CgroupCpuMeasurement> {
+    read_from_cgroups(&cgroup::read_of(pid)?, cpu_count)
+}
+
+#[cfg(target_os = "linux")]
+fn read_from_cgroups(cgroups: &Cgroups, cpu_count: Option<f64>) -> Result<CgroupCpuMeasurement> {
+    read_from_cgroup_dir(cgroups, Path::new(cgroup::SYS_FS_CGROUP_DIR), cpu_count)
+}
+
+#[cfg(target_os = "linux")]
+fn read_from_cgroup_dir(
+    cgroups: &Cgroups,
+    cgroup_dir: &Path,
+    cpu_count: Option<f64>,
+) -> Result<CgroupCpuMeasurement> {
     use super::cgroup_v1::read_and_parse_v1_sys_stat;
     use super::cgroup_v2::read_and_parse_v2_sys_stat;
 
-    let v2_sys_fs_file = Path::new("/sys/fs/cgroup/cpu.stat");
-    if v2_sys_fs_file.exists() {
-        let v2_sys_fs_cpu_max_file = Path::new("/sys/fs/cgroup/cpu.max");
-        return read_and_parse_v2_sys_stat(&v2_sys_fs_file, v2_sys_fs_cpu_max_file, cpu_count);
+    // On hybrid systems the unified hierarchy is present as well, but the cpu controllers are
+    // bound to the v1 hierarchies.
+    if let Some(v1_sys_fs_dir) = cgroups.controller_dir_in(cgroup_dir, "cpuacct") {
+        if dir_exists(&v1_sys_fs_dir) {
+            let v1_sys_fs_cpu_dir = cgroups
+                .controller_dir_in(cgroup_dir, "cpu")
+                .unwrap_or_else(|| cgroup_dir.join("cpu"));
+            return read_and_parse_v1_sys_stat(
+                &v1_sys_fs_dir,
+                &v1_sys_fs_cpu_dir.join("cpu.cfs_period_us"),
+                &v1_sys_fs_cpu_dir.join("cpu.cfs_quota_us"),
+                &v1_sys_fs_cpu_dir.join("cpu.stat"),
+                cpu_count,
+            );
+        }
     }
 
-    let v1_sys_fs_dir = Path::new("/sys/fs/cgroup/cpuacct/");
-    if dir_exists(v1_sys_fs_dir) {
-        return read_and_parse_v1_sys_stat(
-            &v1_sys_fs_dir,
-            &Path::new("/sys/fs/cgroup/cpu/cpu.cfs_period_us"),
-            &Path::new("/sys/fs/cgroup/cpu/cpu.cfs_quota_us"),
-            cpu_count,
-        );
+    if let Some(v2_sys_fs_dir) = cgroups.unified_dir_in(cgroup_dir) {
+        let v2_sys_fs_file = v2_sys_fs_dir.join("cpu.stat");
+        if v2_sys_fs_file.exists() {
+            let v2_sys_fs_cpu_max_file = v2_sys_fs_dir.join("cpu.max");
+            return read_and_parse_v2_sys_stat(&v2_sys_fs_file, &v2_sys_fs_cpu_max_file, cpu_count);
+        }
     }
 
-    Err(ProbeError::UnexpectedContent(format!(
-        "Directory `{}` and file `{}` not found",
-        v1_sys_fs_dir.to_str().unwrap_or("unknown path"),
-        v2_sys_fs_file.to_str().unwrap_or("unknown path")
-    )))
+    Err(ProbeError::UnexpectedContent(
+        "No cgroup v1 or v2 cpu stats found".to_owned(),
+    ))
 }
 
 #[cfg(test)]
 #[cfg(target_os = "linux")]
 mod test {
-    use super::{CgroupCpuMeasurement, CgroupCpuStat};
+    use super::{CgroupCpuMeasurement, CgroupCpuStat, CgroupCpuThrottling};
+    use crate::cgroup::os::read_and_parse_proc_cgroup;
     use crate::error::ProbeError;
+    use std::path::Path;
 
     #[test]
     fn test_read() {
@@ -124,6 +217,37 @@ mod test {
         assert!(super::read(Some(0.5)).is_ok());
     }
 
+    #[test]
+    fn test_read_of() {
+        let pid = unsafe { libc::getpid() };
+        assert!(super::read_of(pid, None).is_ok());
+    }
+
+    #[test]
+    fn test_read_of_invalid_pid() {
+        match super::read_of(0, None) {
+            Err(ProbeError::IO(_, _)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_from_cgroup_dir_hybrid() {
+        let cgroups =
+            read_and_parse_proc_cgroup(Path::new("fixtures/linux/proc/self/cgroup/hybrid"))
+                .unwrap();
+        let measurement = super::read_from_cgroup_dir(
+            &cgroups,
+            Path::new("fixtures/linux/sys/fs/cgroup_hybrid"),
+            Some(1.0),
+        )
+        .unwrap();
+
+        // Read from the v1 cpu controller, while the unified hierarchy has a cpu.stat as well
+        assert_eq!(152657213021, measurement.stat.total_usage);
+        assert_eq!(30, measurement.stat.throttling.unwrap().nr_throttled);
+    }
+
     #[test]
     fn test_calculate_per_minute_wrong_times() {
         let measurement1 = CgroupCpuMeasurement {
@@ -132,6 +256,7 @@ mod test {
                 total_usage: 0,
                 user: 0,
                 system: 0,
+                throttling: None,
             },
         };
 
@@ -141,6 +266,7 @@ mod test {
                 total_usage: 0,
                 user: 0,
                 system: 0,
+                throttling: None,
             },
         };
 
@@ -158,6 +284,7 @@ mod test {
                 total_usage: 6380,
                 user: 1000,
                 system: 1200,
+                throttling: None,
             },
         };
 
@@ -167,6 +294,7 @@ mod test {
                 total_usage: 6440,
                 user: 1006,
                 system: 1206,
+                throttling: None,
             },
         };
 
@@ -174,6 +302,7 @@ mod test {
             total_usage: 60,
             user: 6,
             system: 6,
+            throttling: None,
         };
 
         let stat = measurement1.calculate_per_minute(&measurement2).unwrap();
@@ -189,6 +318,7 @@ mod test {
                 total_usage: 1_000_000_000,
                 user: 10000_000_000,
                 system: 12000_000_000,
+                throttling: None,
             },
         };
 
@@ -198,6 +328,7 @@ mod test {
                 total_usage: 1_500_000_000,
                 user: 10060_000_000,
                 system: 12060_000_000,
+                throttling: None,
             },
         };
 
@@ -205,6 +336,7 @@ mod test {
             total_usage: 1_000_000_000,
             user: 120_000_000,
             system: 120_000_000,
+            throttling: None,
         };
 
         let stat = measurement1.calculate_per_minute(&measurement2).unwrap();
@@ -220,6 +352,7 @@ mod test {
                 total_usage: 63800_000_000,
                 user: 10000_000_000,
                 system: 12000_000_000,
+                throttling: None,
             },
         };
 
@@ -229,6 +362,7 @@ mod test {
                 total_usage: 10400_000_000,
                 user: 1060_000_000,
                 system: 1260_000_000,
+                throttling: None,
             },
         };
 
@@ -244,6 +378,7 @@ mod test {
             total_usage: 24000000000,
             user: 16800000000,
             system: 1200000000,
+            throttling: None,
         };
 
         let in_percentages = stat.in_percentages();
@@ -266,6 +401,7 @@ mod test {
             total_usage: 24000000000,
             user: 17100000000,
             system: 900000000,
+            throttling: None,
         };
 
         let in_percentages = stat.in_percentages();
@@ -281,4 +417,46 @@ mod test {
         assert!(in_percentages.system > 1.4);
         assert!(in_percentages.system <= 1.5);
     }
+
+    #[test]
+    fn test_calculate_per_minute_throttling_missing() {
+        let measurement1 = CgroupCpuMeasurement {
+            precise_time_ns: 60_000_000_000,
+            stat: CgroupCpuStat {
+                total_usage: 6380,
+                user: 1000,
+                system: 1200,
+                throttling: None,
+            },
+        };
+
+        let measurement2 = CgroupCpuMeasurement {
+            precise_time_ns: 120_000_000_000,
+            stat: CgroupCpuStat {
+                total_usage: 6440,
+                user: 1006,
+                system: 1206,
+                throttling: Some(CgroupCpuThrottling {
+                    nr_periods: 10,
+                    nr_throttled: 1,
+                    throttled_time: 1000,
+                }),
+            },
+        };
+
+        let stat = measurement1.calculate_per_minute(&measurement2).unwrap();
+        assert_eq!(stat.throttling, None);
+        assert_eq!(stat.in_percentages().throttled, None);
+    }
+
+    #[test]
+    fn test_throttled_percentage_without_periods() {
+        let throttling = CgroupCpuThrottling {
+            nr_periods: 0,
+            nr_throttled: 0,
+            throttled_time: 0,
+        };
+
+        assert_eq!(throttling.throttled_percentage(), 0.0);
+    }
 }
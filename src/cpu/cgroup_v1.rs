@@ -1,4 +1,4 @@
-use super::cgroup::{CgroupCpuMeasurement, CgroupCpuStat};
+use super::cgroup::{CgroupCpuMeasurement, CgroupCpuStat, CgroupCpuThrottling};
 use crate::error::ProbeError;
 use crate::{
     file_to_buf_reader, file_to_string, parse_u64, path_to_string, precise_time_ns,
@@ -8,12 +8,14 @@ use std::io::BufRead;
 use std::path::Path;
 
 const CPU_SYS_V1_NUMBER_OF_FIELDS: usize = 2;
+const CPU_SYS_V1_NUMBER_OF_THROTTLING_FIELDS: usize = 3;
 
 #[cfg(target_os = "linux")]
 pub fn read_and_parse_v1_sys_stat(
     path: &Path,
     cpu_period_path: &Path,
     cpu_quota_path: &Path,
+    cpu_stat_path: &Path,
     mut cpu_count: Option<f64>,
 ) -> Result<CgroupCpuMeasurement> {
     let time = precise_time_ns();
@@ -39,6 +41,7 @@ pub fn read_and_parse_v1_sys_stat(
         total_usage,
         user: 0,
         system: 0,
+        throttling: None,
     };
 
     let mut fields_encountered = 0;
@@ -68,6 +71,12 @@ pub fn read_and_parse_v1_sys_stat(
             "Did not encounter all expected fields".to_owned(),
         ));
     }
+
+    // The throttling statistics are reported by the cpu controller, which is not always mounted.
+    if cpu_stat_path.exists() {
+        cpu.throttling = Some(read_and_parse_v1_throttling(cpu_stat_path)?);
+    }
+
     let measurement = CgroupCpuMeasurement {
         precise_time_ns: time,
         stat: cpu.by_cpu_count(cpu_count),
@@ -75,10 +84,58 @@ pub fn read_and_parse_v1_sys_stat(
     Ok(measurement)
 }
 
+#[cfg(target_os = "linux")]
+fn read_and_parse_v1_throttling(path: &Path) -> Result<CgroupCpuThrottling> {
+    let reader = file_to_buf_reader(path)?;
+
+    let mut throttling = CgroupCpuThrottling {
+        nr_periods: 0,
+        nr_throttled: 0,
+        throttled_time: 0,
+    };
+
+    let mut fields_encountered = 0;
+    for line in reader.lines() {
+        let line = line.map_err(|e| ProbeError::IO(e, path_to_string(path)))?;
+        let segments: Vec<&str> = line.split_whitespace().collect();
+        if segments.len() < 2 {
+            continue;
+        }
+        let value = parse_u64(segments[1])?;
+        fields_encountered += match segments[0] {
+            "nr_periods" => {
+                throttling.nr_periods = value;
+                1
+            }
+            "nr_throttled" => {
+                throttling.nr_throttled = value;
+                1
+            }
+            "throttled_time" => {
+                throttling.throttled_time = value;
+                1
+            }
+            _ => 0,
+        };
+
+        if fields_encountered == CPU_SYS_V1_NUMBER_OF_THROTTLING_FIELDS {
+            break;
+        }
+    }
+
+    if fields_encountered != CPU_SYS_V1_NUMBER_OF_THROTTLING_FIELDS {
+        return Err(ProbeError::UnexpectedContent(
+            "Did not encounter all expected fields".to_owned(),
+        ));
+    }
+    Ok(throttling)
+}
+
 #[cfg(test)]
 #[cfg(target_os = "linux")]
 mod test {
     use super::read_and_parse_v1_sys_stat;
+    use crate::cpu::cgroup::CgroupCpuThrottling;
     use crate::error::ProbeError;
     use std::path::Path;
 
@@ -88,6 +145,7 @@ mod test {
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpuacct_1/"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
+            &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
             None,
         )
         .unwrap();
@@ -103,6 +161,7 @@ mod test {
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpuacct_1/"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.cfs_period_us"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.cfs_quota_us.one_cpu"),
+            &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
             None,
         )
         .unwrap();
@@ -118,6 +177,7 @@ mod test {
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpuacct_1/"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.cfs_period_us"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.cfs_quota_us.two_cpu"),
+            &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
             None,
         )
         .unwrap();
@@ -133,6 +193,7 @@ mod test {
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpuacct_1/"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.cfs_period_us"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.cfs_quota_us.half_cpu"),
+            &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
             None,
         )
         .unwrap();
@@ -148,6 +209,7 @@ mod test {
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpuacct_1/"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.cfs_period_us"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.cfs_quota_us.minus_one"),
+            &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
             None,
         )
         .unwrap();
@@ -164,6 +226,7 @@ mod test {
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpuacct_1/"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.cfs_period_us"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.cfs_quota_us.minus_one"),
+            &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
             Some(1.0),
         )
         .unwrap();
@@ -179,6 +242,7 @@ mod test {
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpuacct_1/"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.cfs_period_us"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.cfs_quota_us.minus_one"),
+            &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
             Some(0.5),
         )
         .unwrap();
@@ -194,6 +258,7 @@ mod test {
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpuacct_1/"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.cfs_period_us"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.cfs_quota_us.minus_one"),
+            &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
             Some(2.0),
         )
         .unwrap();
@@ -203,12 +268,61 @@ mod test {
         assert_eq!(cpu.system, 490000000);
     }
 
+    #[test]
+    fn test_read_v1_sys_measurement_throttling() {
+        let measurement = read_and_parse_v1_sys_stat(
+            Path::new("fixtures/linux/sys/fs/cgroup_v1/cpuacct_1/"),
+            Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.cfs_period_us"),
+            Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.cfs_quota_us.two_cpu"),
+            Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.stat_1"),
+            None,
+        )
+        .unwrap();
+        let cpu = measurement.stat;
+        assert_eq!(cpu.total_usage, 76328606511);
+        // Throttling is not divided by the number of CPUs
+        let expected = CgroupCpuThrottling {
+            nr_periods: 1200,
+            nr_throttled: 30,
+            throttled_time: 4_520_000_000,
+        };
+        assert_eq!(cpu.throttling, Some(expected));
+    }
+
+    #[test]
+    fn test_read_v1_sys_measurement_without_throttling() {
+        let measurement = read_and_parse_v1_sys_stat(
+            Path::new("fixtures/linux/sys/fs/cgroup_v1/cpuacct_1/"),
+            Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
+            Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
+            Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
+            None,
+        )
+        .unwrap();
+        assert_eq!(measurement.stat.throttling, None);
+    }
+
+    #[test]
+    fn test_read_v1_sys_measurement_throttling_garbage() {
+        match read_and_parse_v1_sys_stat(
+            Path::new("fixtures/linux/sys/fs/cgroup_v1/cpuacct_1/"),
+            Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
+            Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
+            Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.stat_garbage"),
+            None,
+        ) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
     #[test]
     fn test_read_v1_sys_wrong_path() {
         match read_and_parse_v1_sys_stat(
             &Path::new("bananas"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
+            &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
             None,
         ) {
             Err(ProbeError::IO(_, _)) => (),
@@ -222,6 +336,7 @@ mod test {
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpuacct_incomplete/"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
+            &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
             None,
         ) {
             Err(ProbeError::UnexpectedContent(_)) => (),
@@ -235,6 +350,7 @@ mod test {
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpuacct_garbage/"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
+            &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
             None,
         ) {
             Err(ProbeError::UnexpectedContent(_)) => (),
@@ -248,6 +364,7 @@ mod test {
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpuacct_1/"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
+            &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
             None,
         )
         .unwrap();
@@ -256,6 +373,7 @@ mod test {
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpuacct_2/"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
+            &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
             None,
         )
         .unwrap();
@@ -282,6 +400,7 @@ mod test {
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpuacct_1/"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.cfs_period_us"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.cfs_quota_us.two_cpu"),
+            &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
             None,
         )
         .unwrap();
@@ -290,6 +409,7 @@ mod test {
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpuacct_2/"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.cfs_period_us"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.cfs_quota_us.two_cpu"),
+            &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
             None,
         )
         .unwrap();
@@ -316,6 +436,7 @@ mod test {
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpuacct_1/"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.cfs_period_us"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.cfs_quota_us.half_cpu"),
+            &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
             None,
         )
         .unwrap();
@@ -324,6 +445,7 @@ mod test {
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpuacct_2/"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.cfs_period_us"),
             &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.cfs_quota_us.half_cpu"),
+            &Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
             None,
         )
         .unwrap();
@@ -343,4 +465,38 @@ mod test {
         assert!(in_percentages.system > 0.76);
         assert!(in_percentages.system < 0.77);
     }
+
+    #[test]
+    fn test_throttling_per_minute_integration_v1() {
+        let mut measurement1 = read_and_parse_v1_sys_stat(
+            Path::new("fixtures/linux/sys/fs/cgroup_v1/cpuacct_1/"),
+            Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
+            Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
+            Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.stat_1"),
+            None,
+        )
+        .unwrap();
+        measurement1.precise_time_ns = 60_000_000_000;
+        let mut measurement2 = read_and_parse_v1_sys_stat(
+            Path::new("fixtures/linux/sys/fs/cgroup_v1/cpuacct_2/"),
+            Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
+            Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/does_not_exist"),
+            Path::new("fixtures/linux/sys/fs/cgroup_v1/cpu_quota/cpu.stat_2"),
+            None,
+        )
+        .unwrap();
+        measurement2.precise_time_ns = 120_000_000_000;
+
+        let stat = measurement1.calculate_per_minute(&measurement2).unwrap();
+        let expected = CgroupCpuThrottling {
+            nr_periods: 600,
+            nr_throttled: 60,
+            throttled_time: 5_460_000_000,
+        };
+        assert_eq!(stat.throttling, Some(expected));
+
+        let throttled = stat.in_percentages().throttled.unwrap();
+        assert!(throttled > 9.99);
+        assert!(throttled <= 10.0);
+    }
 }
@@ -1,10 +1,11 @@
-use super::cgroup::{CgroupCpuMeasurement, CgroupCpuStat};
+use super::cgroup::{CgroupCpuMeasurement, CgroupCpuStat, CgroupCpuThrottling};
 use crate::error::ProbeError;
 use crate::{file_to_buf_reader, parse_u64, path_to_string, precise_time_ns, Result};
 use std::io::BufRead;
 use std::path::Path;
 
 const CPU_SYS_V2_NUMBER_OF_FIELDS: usize = 3;
+const CPU_SYS_V2_NUMBER_OF_THROTTLING_FIELDS: usize = 3;
 
 #[cfg(target_os = "linux")]
 pub fn read_and_parse_v2_sys_stat(
@@ -38,9 +39,17 @@ pub fn read_and_parse_v2_sys_stat(
         total_usage: 0,
         user: 0,
         system: 0,
+        throttling: None,
+    };
+    // The throttling fields are only present when the cpu controller is enabled for the cgroup.
+    let mut throttling = CgroupCpuThrottling {
+        nr_periods: 0,
+        nr_throttled: 0,
+        throttled_time: 0,
     };
 
     let mut fields_encountered = 0;
+    let mut throttling_fields_encountered = 0;
     for line in reader.lines() {
         let line = line.map_err(|e| ProbeError::IO(e, path_to_string(path)))?;
         let segments: Vec<&str> = line.split_whitespace().collect();
@@ -60,8 +69,25 @@ pub fn read_and_parse_v2_sys_stat(
             }
             _ => 0,
         };
+        throttling_fields_encountered += match segments[0] {
+            "nr_periods" => {
+                throttling.nr_periods = value;
+                1
+            }
+            "nr_throttled" => {
+                throttling.nr_throttled = value;
+                1
+            }
+            "throttled_usec" => {
+                throttling.throttled_time = value * 1_000;
+                1
+            }
+            _ => 0,
+        };
 
-        if fields_encountered == CPU_SYS_V2_NUMBER_OF_FIELDS {
+        if fields_encountered == CPU_SYS_V2_NUMBER_OF_FIELDS
+            && throttling_fields_encountered == CPU_SYS_V2_NUMBER_OF_THROTTLING_FIELDS
+        {
             break;
         }
     }
@@ -71,6 +97,9 @@ pub fn read_and_parse_v2_sys_stat(
             "Did not encounter all expected fields".to_owned(),
         ));
     }
+    if throttling_fields_encountered == CPU_SYS_V2_NUMBER_OF_THROTTLING_FIELDS {
+        cpu.throttling = Some(throttling);
+    }
     let measurement = CgroupCpuMeasurement {
         precise_time_ns: time,
         stat: cpu.by_cpu_count(cpu_count),
@@ -82,6 +111,7 @@ pub fn read_and_parse_v2_sys_stat(
 #[cfg(target_os = "linux")]
 mod test {
     use super::read_and_parse_v2_sys_stat;
+    use crate::cpu::cgroup::CgroupCpuThrottling;
     use crate::error::ProbeError;
     use std::{option::Option::None, path::Path};
 
@@ -169,6 +199,35 @@ mod test {
         assert_eq!(cpu.system, 235340000);
     }
 
+    #[test]
+    fn test_read_v2_sys_measurement_throttling() {
+        let measurement = read_and_parse_v2_sys_stat(
+            Path::new("fixtures/linux/sys/fs/cgroup_v2/cpu.stat_throttled_1"),
+            Path::new("fixtures/linux/sys/fs/cgroup_v2/cpu.max_half"),
+            None,
+        )
+        .unwrap();
+        let expected = CgroupCpuThrottling {
+            nr_periods: 1200,
+            nr_throttled: 30,
+            throttled_time: 4_520_000_000,
+        };
+        // Throttling is not divided by the number of CPUs
+        assert_eq!(measurement.stat.throttling, Some(expected));
+    }
+
+    #[test]
+    fn test_read_v2_sys_measurement_without_throttling() {
+        let measurement = read_and_parse_v2_sys_stat(
+            Path::new("fixtures/linux/sys/fs/cgroup_v2/cpu.stat_without_throttling"),
+            Path::new("fixtures/linux/sys/fs/cgroup_v2/cpu.max_default"),
+            None,
+        )
+        .unwrap();
+        assert_eq!(measurement.stat.total_usage, 171462000);
+        assert_eq!(measurement.stat.throttling, None);
+    }
+
     #[test]
     fn test_read_v2_sys_wrong_path() {
         match read_and_parse_v2_sys_stat(&Path::new("bananas"), &Path::new("potato"), None) {
@@ -273,4 +332,34 @@ mod test {
         assert!(in_percentages.system > 0.27);
         assert!(in_percentages.system < 0.28);
     }
+
+    #[test]
+    fn test_throttling_per_minute_integration_v2() {
+        let mut measurement1 = read_and_parse_v2_sys_stat(
+            Path::new("fixtures/linux/sys/fs/cgroup_v2/cpu.stat_throttled_1"),
+            Path::new("fixtures/linux/sys/fs/cgroup_v2/cpu.max_half"),
+            None,
+        )
+        .unwrap();
+        measurement1.precise_time_ns = 60_000_000_000;
+        let mut measurement2 = read_and_parse_v2_sys_stat(
+            Path::new("fixtures/linux/sys/fs/cgroup_v2/cpu.stat_throttled_2"),
+            Path::new("fixtures/linux/sys/fs/cgroup_v2/cpu.max_half"),
+            None,
+        )
+        .unwrap();
+        measurement2.precise_time_ns = 120_000_000_000;
+
+        let stat = measurement1.calculate_per_minute(&measurement2).unwrap();
+        let expected = CgroupCpuThrottling {
+            nr_periods: 600,
+            nr_throttled: 60,
+            throttled_time: 5_460_000_000,
+        };
+        assert_eq!(stat.throttling, Some(expected));
+
+        let throttled = stat.in_percentages().throttled.unwrap();
+        assert!(throttled > 9.99);
+        assert!(throttled <= 10.0);
+    }
 }
@@ -2,3 +2,5 @@ pub mod cgroup;
 mod cgroup_v1;
 mod cgroup_v2;
 pub mod proc;
+pub mod process;
+pub mod schedstat;
@@ -1,10 +1,14 @@
-use super::super::{calculate_time_difference, time_adjusted, Result};
+use super::super::{calculate_time_difference, time_adjusted, ProbeError, Result};
+use std::collections::HashMap;
 
 /// Measurement of cpu stats at a certain time
 #[derive(Debug, PartialEq)]
 pub struct CpuMeasurement {
     pub precise_time_ns: u64,
     pub stat: CpuStat,
+    /// Kernel activity read from the same snapshot as the cpu stats, `None` when not all kernel
+    /// activity fields could be read
+    pub kernel_activity: Option<KernelActivity>,
 }
 
 impl CpuMeasurement {
@@ -15,75 +19,87 @@ impl CpuMeasurement {
         let time_difference =
             calculate_time_difference(self.precise_time_ns, next_measurement.precise_time_ns)?;
 
-        Ok(CpuStat {
-            total: time_adjusted(
-                "total",
-                next_measurement.stat.total,
-                self.stat.total,
-                time_difference,
-            )?,
-            user: time_adjusted(
-                "user",
-                next_measurement.stat.user,
-                self.stat.user,
-                time_difference,
-            )?,
-            nice: time_adjusted(
-                "nice",
-                next_measurement.stat.nice,
-                self.stat.nice,
-                time_difference,
-            )?,
-            system: time_adjusted(
-                "system",
-                next_measurement.stat.system,
-                self.stat.system,
-                time_difference,
-            )?,
-            idle: time_adjusted(
-                "idle",
-                next_measurement.stat.idle,
-                self.stat.idle,
-                time_difference,
-            )?,
-            iowait: time_adjusted(
-                "iowait",
-                next_measurement.stat.iowait,
-                self.stat.iowait,
-                time_difference,
-            )?,
-            irq: time_adjusted(
-                "irq",
-                next_measurement.stat.irq,
-                self.stat.irq,
-                time_difference,
-            )?,
-            softirq: time_adjusted(
-                "softirq",
-                next_measurement.stat.softirq,
-                self.stat.softirq,
-                time_difference,
-            )?,
-            steal: time_adjusted(
-                "steal",
-                next_measurement.stat.steal,
-                self.stat.steal,
-                time_difference,
-            )?,
-            guest: time_adjusted(
-                "guest",
-                next_measurement.stat.guest,
-                self.stat.guest,
-                time_difference,
-            )?,
-            guestnice: time_adjusted(
-                "guestnice",
-                next_measurement.stat.guestnice,
-                self.stat.guestnice,
-                time_difference,
-            )?,
-        })
+        self.stat
+            .per_minute(&next_measurement.stat, time_difference)
     }
+
+    /// Calculate the kernel activity based on this measurement and a measurement in the future.
+    /// The counters are converted to rates per minute, the number of running and blocked
+    /// processes and the boot time are taken from the next measurement as they are. Returns an
+    /// error when either measurement has no kernel activity.
+    pub fn calculate_kernel_activity_per_minute(
+        &self,
+        next_measurement: &CpuMeasurement,
+    ) -> Result<KernelActivity> {
+        let time_difference =
+            calculate_time_difference(self.precise_time_ns, next_measurement.precise_time_ns)?;
+
+        match (&self.kernel_activity, &next_measurement.kernel_activity) {
+            (Some(kernel_activity), Some(next_kernel_activity)) => {
+                kernel_activity.per_minute(next_kernel_activity, time_difference)
+            }
+            _ => Err(ProbeError::UnexpectedContent(
+                "No kernel activity in measurement".to_owned(),
+            )),
+        }
+    }
+}
+
+pub type Cores = HashMap<usize, CpuStat>;
+
+/// Measurement of per-core cpu stats at a certain time
+#[derive(Debug, PartialEq)]
+pub struct PerCoreCpuMeasurement {
+    pub precise_time_ns: u64,
+    pub cores: Cores,
+}
+
+impl PerCoreCpuMeasurement {
+    /// Calculate the cpu stats per core based on this measurement and a measurement in the
+    /// future. Cores that are not present in both measurements, because they went offline or
+    /// came online in between, are left out of the result.
+    pub fn calculate_per_minute(
+        &self,
+        next_measurement: &PerCoreCpuMeasurement,
+    ) -> Result<PerCoreCpuStat> {
+        let time_difference =
+            calculate_time_difference(self.precise_time_ns, next_measurement.precise_time_ns)?;
+
+        let mut cores = Cores::new();
+
+        for (index, stat) in self.cores.iter() {
+            if let Some(next_stat) = next_measurement.cores.get(index) {
+                cores.insert(*index, stat.per_minute(next_stat, time_difference)?);
+            }
+        }
+
+        Ok(PerCoreCpuStat { cores })
+    }
+}
+
+/// Cpu stats per core for a minute
+#[derive(Debug, PartialEq)]
+pub struct PerCoreCpuStat {
+    pub cores: Cores,
+}
+
+impl PerCoreCpuStat {
+    /// Calculate the weight of the various components in percentages for every core
+    pub fn in_percentages(&self) -> PerCoreCpuStatPercentages {
+        PerCoreCpuStatPercentages {
+            cores: self
+                .cores
+                .iter()
+                .map(|(index, stat)| (*index, stat.in_percentages()))
+                .collect(),
+        }
+    }
+}
+
+/// Per-core cpu stats converted to percentages
+#[derive(Debug, PartialEq)]
+pub struct PerCoreCpuStatPercentages {
+    pub cores: HashMap<usize, CpuStatPercentages>,
 }
 
 /// Cpu stats for a minute
@@ -123,6 +139,27 @@ impl CpuStat {
     fn percentage_of_total(&self, value: u64) -> f32 {
         (value as f64 / self.total as f64 * 100.0) as f32
     }
+
+    fn per_minute(&self, next_stat: &CpuStat, time_difference: u64) -> Result<CpuStat> {
+        Ok(CpuStat {
+            total: time_adjusted("total", next_stat.total, self.total, time_difference)?,
+            user: time_adjusted("user", next_stat.user, self.user, time_difference)?,
+            nice: time_adjusted("nice", next_stat.nice, self.nice, time_difference)?,
+            system: time_adjusted("system", next_stat.system, self.system, time_difference)?,
+            idle: time_adjusted("idle", next_stat.idle, self.idle, time_difference)?,
+            iowait: time_adjusted("iowait", next_stat.iowait, self.iowait, time_difference)?,
+            irq: time_adjusted("irq", next_stat.irq, self.irq, time_difference)?,
+            softirq: time_adjusted("softirq", next_stat.softirq, self.softirq, time_difference)?,
+            steal: time_adjusted("steal", next_stat.steal, self.steal, time_difference)?,
+            guest: time_adjusted("guest", next_stat.guest, self.guest, time_difference)?,
+            guestnice: time_adjusted(
+                "guestnice",
+                next_stat.guestnice,
+                self.guestnice,
+                time_difference,
+            )?,
+        })
+    }
 }
 
 /// Cpu stats converted to percentages
@@ -141,39 +178,199 @@ pub struct CpuStatPercentages {
     pub guestnice: f32,
 }
 
+/// Kernel activity counters and gauges
+#[derive(Debug, PartialEq)]
+pub struct KernelActivity {
+    /// Number of context switches (`ctxt`)
+    pub context_switches: u64,
+    /// Number of serviced interrupts (`intr`)
+    pub interrupts: u64,
+    /// Number of processes and threads created (`processes`)
+    pub forks: u64,
+    /// Number of processes in a runnable state
+    pub procs_running: u64,
+    /// Number of processes blocked waiting for I/O
+    pub procs_blocked: u64,
+    /// Boot time in seconds since the Unix epoch (`btime`)
+    pub boot_time: u64,
+}
+
+impl KernelActivity {
+    fn per_minute(
+        &self,
+        next_stat: &KernelActivity,
+        time_difference: u64,
+    ) -> Result<KernelActivity> {
+        Ok(KernelActivity {
+            context_switches: time_adjusted(
+                "context_switches",
+                next_stat.context_switches,
+                self.context_switches,
+                time_difference,
+            )?,
+            interrupts: time_adjusted(
+                "interrupts",
+                next_stat.interrupts,
+                self.interrupts,
+                time_difference,
+            )?,
+            forks: time_adjusted("forks", next_stat.forks, self.forks, time_difference)?,
+            procs_running: next_stat.procs_running,
+            procs_blocked: next_stat.procs_blocked,
+            boot_time: next_stat.boot_time,
+        })
+    }
+}
+
 /// Read the current CPU stats of the system.
 #[cfg(target_os = "linux")]
 pub fn read() -> Result<CpuMeasurement> {
     os::read()
 }
 
+/// Read the current CPU stats of every core of the system.
+#[cfg(target_os = "linux")]
+pub fn read_per_core() -> Result<PerCoreCpuMeasurement> {
+    os::read_per_core()
+}
+
+/// Read the boot time of the system in seconds since the Unix epoch.
+#[cfg(target_os = "linux")]
+pub(crate) fn read_boot_time() -> Result<u64> {
+    read()?
+        .kernel_activity
+        .map(|kernel_activity| kernel_activity.boot_time)
+        .ok_or_else(|| ProbeError::UnexpectedContent("No boot time found".to_owned()))
+}
+
 #[cfg(target_os = "linux")]
 mod os {
     use super::super::super::{
         file_to_buf_reader, parse_u64, path_to_string, precise_time_ns, Result,
     };
-    use super::{CpuMeasurement, CpuStat};
+    use super::{Cores, CpuMeasurement, CpuStat, KernelActivity, PerCoreCpuMeasurement};
     use crate::error::ProbeError;
     use std::io::BufRead;
     use std::path::Path;
 
+    const KERNEL_ACTIVITY_NUMBER_OF_FIELDS: usize = 6;
+
     #[inline]
     pub fn read() -> Result<CpuMeasurement> {
         read_and_parse_proc_stat(&Path::new("/proc/stat"))
     }
 
+    #[inline]
+    pub fn read_per_core() -> Result<PerCoreCpuMeasurement> {
+        read_and_parse_proc_stat_per_core(Path::new("/proc/stat"))
+    }
+
     pub fn read_and_parse_proc_stat(path: &Path) -> Result<CpuMeasurement> {
-        let mut line = String::new();
-        // columns: user nice system idle iowait irq softirq
-        let mut reader = file_to_buf_reader(path)?;
+        let reader = file_to_buf_reader(path)?;
+        let time = precise_time_ns();
+
+        let mut stat = None;
+        let mut kernel_activity = KernelActivity {
+            context_switches: 0,
+            interrupts: 0,
+            forks: 0,
+            procs_running: 0,
+            procs_blocked: 0,
+            boot_time: 0,
+        };
+
+        let mut fields_encountered = 0;
+        for line_result in reader.lines() {
+            let line = line_result.map_err(|e| ProbeError::IO(e, path_to_string(path)))?;
+            let segments: Vec<&str> = line.split_whitespace().collect();
+
+            match segments.as_slice() {
+                // columns: user nice system idle iowait irq softirq
+                ["cpu", stats @ ..] => stat = Some(parse_cpu_stat(stats)?),
+                // The `intr` line contains the total followed by the count per interrupt, the
+                // total is the only one we need.
+                [field, value, ..] => {
+                    if parse_kernel_activity_field(&mut kernel_activity, field, value) {
+                        fields_encountered += 1;
+                    }
+                }
+                _ => continue,
+            }
+        }
+
+        let stat =
+            stat.ok_or_else(|| ProbeError::UnexpectedContent("No cpu stats found".to_owned()))?;
+
+        // Missing kernel activity should not prevent reading the cpu stats.
+        Ok(CpuMeasurement {
+            precise_time_ns: time,
+            stat,
+            kernel_activity: if fields_encountered == KERNEL_ACTIVITY_NUMBER_OF_FIELDS {
+                Some(kernel_activity)
+            } else {
+                None
+            },
+        })
+    }
+
+    pub fn read_and_parse_proc_stat_per_core(path: &Path) -> Result<PerCoreCpuMeasurement> {
+        let reader = file_to_buf_reader(path)?;
         let time = precise_time_ns();
 
-        reader
-            .read_line(&mut line)
-            .map_err(|e| ProbeError::IO(e, path_to_string(path)))?;
+        let mut cores = Cores::new();
+        for line_result in reader.lines() {
+            let line = line_result.map_err(|e| ProbeError::IO(e, path_to_string(path)))?;
+            let segments: Vec<&str> = line.split_whitespace().collect();
 
-        let stats: Vec<&str> = line.split_whitespace().skip(1).collect();
+            // Only the `cpuN` lines, the first `cpu` line is the aggregate of all cores.
+            let index = match segments.first().and_then(|name| name.strip_prefix("cpu")) {
+                Some(index) if !index.is_empty() => index.parse::<usize>().map_err(|_| {
+                    ProbeError::UnexpectedContent(format!("Could not parse '{}' as core", index))
+                })?,
+                _ => continue,
+            };
 
+            cores.insert(index, parse_cpu_stat(&segments[1..])?);
+        }
+
+        if cores.is_empty() {
+            return Err(ProbeError::UnexpectedContent(
+                "No per-core cpu stats found".to_owned(),
+            ));
+        }
+
+        Ok(PerCoreCpuMeasurement {
+            precise_time_ns: time,
+            cores,
+        })
+    }
+
+    /// Set the kernel activity field of a line, returns whether a field was set.
+    fn parse_kernel_activity_field(
+        kernel_activity: &mut KernelActivity,
+        field: &str,
+        value: &str,
+    ) -> bool {
+        let target = match field {
+            "ctxt" => &mut kernel_activity.context_switches,
+            "intr" => &mut kernel_activity.interrupts,
+            "processes" => &mut kernel_activity.forks,
+            "procs_running" => &mut kernel_activity.procs_running,
+            "procs_blocked" => &mut kernel_activity.procs_blocked,
+            "btime" => &mut kernel_activity.boot_time,
+            _ => return false,
+        };
+        match parse_u64(value) {
+            Ok(value) => {
+                *target = value;
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Parse the columns of a `cpu` or `cpuN` line, without the name column.
+    fn parse_cpu_stat(stats: &[&str]) -> Result<CpuStat> {
         let length = stats.len();
         if length < 5 {
             return Err(ProbeError::UnexpectedContent(
@@ -183,8 +380,8 @@ mod os {
 
         let usertime = parse_u64(stats[0])?;
         let nicetime = parse_u64(stats[1])?;
-        let guest = parse_u64(*stats.get(8).unwrap_or(&"0"))?;
-        let guestnice = parse_u64(*stats.get(9).unwrap_or(&"0"))?;
+        let guest = parse_u64(stats.get(8).unwrap_or(&"0"))?;
+        let guestnice = parse_u64(stats.get(9).unwrap_or(&"0"))?;
 
         let mut cpu = CpuStat {
             total: 0,
@@ -193,9 +390,9 @@ mod os {
             system: parse_u64(stats[2])?,
             idle: parse_u64(stats[3])?,
             iowait: parse_u64(stats[4])?,
-            irq: parse_u64(*stats.get(5).unwrap_or(&"0"))?,
-            softirq: parse_u64(*stats.get(6).unwrap_or(&"0"))?,
-            steal: parse_u64(*stats.get(7).unwrap_or(&"0"))?,
+            irq: parse_u64(stats.get(5).unwrap_or(&"0"))?,
+            softirq: parse_u64(stats.get(6).unwrap_or(&"0"))?,
+            steal: parse_u64(stats.get(7).unwrap_or(&"0"))?,
             guest,
             guestnice,
         };
@@ -204,18 +401,17 @@ mod os {
         let virtualtime = cpu.guest + cpu.guestnice;
         cpu.total = cpu.user + cpu.nice + systemalltime + idlealltime + cpu.steal + virtualtime;
 
-        Ok(CpuMeasurement {
-            precise_time_ns: time,
-            stat: cpu,
-        })
+        Ok(cpu)
     }
 }
 
 #[cfg(test)]
 #[cfg(target_os = "linux")]
 mod test {
-    use super::os::read_and_parse_proc_stat;
-    use super::{CpuMeasurement, CpuStat, CpuStatPercentages};
+    use super::os::{read_and_parse_proc_stat, read_and_parse_proc_stat_per_core};
+    use super::{
+        Cores, CpuMeasurement, CpuStat, CpuStatPercentages, KernelActivity, PerCoreCpuMeasurement,
+    };
     use crate::error::ProbeError;
     use std::path::Path;
 
@@ -224,6 +420,11 @@ mod test {
         assert!(super::read().is_ok());
     }
 
+    #[test]
+    fn test_read_cpu_per_core() {
+        assert!(!super::read_per_core().unwrap().cores.is_empty());
+    }
+
     #[test]
     fn test_read_proc_measurement() {
         let measurement =
@@ -285,6 +486,56 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_read_proc_per_core_measurement() {
+        let measurement =
+            read_and_parse_proc_stat_per_core(Path::new("fixtures/linux/cpu/proc_stat")).unwrap();
+        assert_eq!(measurement.cores.len(), 4);
+
+        let cpu0 = measurement.cores.get(&0).unwrap();
+        assert_eq!(cpu0.total, 11249061);
+        assert_eq!(cpu0.user, 561319);
+        assert_eq!(cpu0.nice, 5566);
+        assert_eq!(cpu0.system, 222790);
+        assert_eq!(cpu0.idle, 10391434);
+        assert_eq!(cpu0.iowait, 39539);
+        assert_eq!(cpu0.irq, 0);
+        assert_eq!(cpu0.softirq, 28413);
+        assert_eq!(cpu0.steal, 0);
+        assert_eq!(cpu0.guest, 0);
+        assert_eq!(cpu0.guestnice, 0);
+
+        let cpu3 = measurement.cores.get(&3).unwrap();
+        assert_eq!(cpu3.user, 541179);
+        assert_eq!(cpu3.idle, 65085);
+    }
+
+    #[test]
+    fn test_read_proc_per_core_measurement_wrong_path() {
+        match read_and_parse_proc_stat_per_core(Path::new("bananas")) {
+            Err(ProbeError::IO(_, _)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_proc_per_core_measurement_incomplete() {
+        let path = Path::new("fixtures/linux/cpu/proc_stat_incomplete");
+        match read_and_parse_proc_stat_per_core(path) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_proc_per_core_measurement_garbage() {
+        let path = Path::new("fixtures/linux/cpu/proc_stat_garbage");
+        match read_and_parse_proc_stat_per_core(path) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
     #[test]
     fn test_calculate_per_minute_wrong_times() {
         let measurement1 = CpuMeasurement {
@@ -302,6 +553,7 @@ mod test {
                 guest: 0,
                 guestnice: 0,
             },
+            kernel_activity: Some(helpers::kernel_activity()),
         };
 
         let measurement2 = CpuMeasurement {
@@ -319,6 +571,7 @@ mod test {
                 guest: 0,
                 guestnice: 0,
             },
+            kernel_activity: Some(helpers::kernel_activity()),
         };
 
         match measurement1.calculate_per_minute(&measurement2) {
@@ -344,6 +597,7 @@ mod test {
                 guest: 200,
                 guestnice: 100,
             },
+            kernel_activity: Some(helpers::kernel_activity()),
         };
 
         let measurement2 = CpuMeasurement {
@@ -361,6 +615,7 @@ mod test {
                 guest: 206,
                 guestnice: 106,
             },
+            kernel_activity: Some(helpers::kernel_activity()),
         };
 
         let expected = CpuStat {
@@ -399,6 +654,7 @@ mod test {
                 guest: 200,
                 guestnice: 100,
             },
+            kernel_activity: Some(helpers::kernel_activity()),
         };
 
         let measurement2 = CpuMeasurement {
@@ -416,6 +672,7 @@ mod test {
                 guest: 206,
                 guestnice: 106,
             },
+            kernel_activity: Some(helpers::kernel_activity()),
         };
 
         let expected = CpuStat {
@@ -454,6 +711,7 @@ mod test {
                 guest: 200,
                 guestnice: 100,
             },
+            kernel_activity: Some(helpers::kernel_activity()),
         };
 
         let measurement2 = CpuMeasurement {
@@ -471,6 +729,7 @@ mod test {
                 guest: 206,
                 guestnice: 106,
             },
+            kernel_activity: Some(helpers::kernel_activity()),
         };
 
         match measurement1.calculate_per_minute(&measurement2) {
@@ -604,4 +863,210 @@ mod test {
         assert!(total < 100.1);
         assert!(total > 99.9);
     }
+
+    #[test]
+    fn test_per_core_calculate_per_minute_core_offline() {
+        let mut cores1 = Cores::new();
+        cores1.insert(0, helpers::cpu_stat(1000));
+        cores1.insert(1, helpers::cpu_stat(1000));
+        let measurement1 = PerCoreCpuMeasurement {
+            precise_time_ns: 60_000_000_000,
+            cores: cores1,
+        };
+
+        let mut cores2 = Cores::new();
+        cores2.insert(0, helpers::cpu_stat(1060));
+        let measurement2 = PerCoreCpuMeasurement {
+            precise_time_ns: 90_000_000_000,
+            cores: cores2,
+        };
+
+        let per_minute = measurement1.calculate_per_minute(&measurement2).unwrap();
+        assert_eq!(per_minute.cores.len(), 1);
+        assert_eq!(per_minute.cores.get(&0).unwrap(), &helpers::cpu_stat(120));
+    }
+
+    #[test]
+    fn test_per_core_calculate_per_minute_wrong_times() {
+        let measurement1 = PerCoreCpuMeasurement {
+            precise_time_ns: 90_000_000_000,
+            cores: Cores::new(),
+        };
+        let measurement2 = PerCoreCpuMeasurement {
+            precise_time_ns: 60_000_000_000,
+            cores: Cores::new(),
+        };
+
+        match measurement1.calculate_per_minute(&measurement2) {
+            Err(ProbeError::InvalidInput(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_per_core_in_percentages_integration() {
+        let mut measurement1 =
+            read_and_parse_proc_stat_per_core(Path::new("fixtures/linux/cpu/proc_stat_1")).unwrap();
+        measurement1.precise_time_ns = 60_000_000_000;
+        let mut measurement2 =
+            read_and_parse_proc_stat_per_core(Path::new("fixtures/linux/cpu/proc_stat_2")).unwrap();
+        measurement2.precise_time_ns = 120_000_000_000;
+
+        let stat = measurement1.calculate_per_minute(&measurement2).unwrap();
+        let in_percentages = stat.in_percentages();
+        assert_eq!(in_percentages.cores.len(), 2);
+
+        // Rounding in the floating point calculations can vary, so check if this
+        // is in the correct range.
+        let cpu0 = in_percentages.cores.get(&0).unwrap();
+        assert!(cpu0.user > 4.54);
+        assert!(cpu0.user < 4.56);
+        assert!(cpu0.idle > 94.91);
+        assert!(cpu0.idle < 94.93);
+
+        let cpu1 = in_percentages.cores.get(&1).unwrap();
+        assert!(cpu1.user > 4.68);
+        assert!(cpu1.user < 4.70);
+        assert!(cpu1.system > 2.57);
+        assert!(cpu1.system < 2.59);
+    }
+
+    #[test]
+    fn test_read_cpu_kernel_activity() {
+        assert!(super::read().unwrap().kernel_activity.is_some());
+    }
+
+    #[test]
+    fn test_read_proc_kernel_activity() {
+        let measurement =
+            read_and_parse_proc_stat(Path::new("fixtures/linux/cpu/proc_stat")).unwrap();
+
+        let expected = KernelActivity {
+            context_switches: 1555131703,
+            interrupts: 70555899,
+            forks: 661401,
+            procs_running: 1,
+            procs_blocked: 0,
+            boot_time: 1444814406,
+        };
+        assert_eq!(measurement.kernel_activity, Some(expected));
+    }
+
+    #[test]
+    fn test_read_proc_kernel_activity_incomplete() {
+        // The cpu stats can still be read without kernel activity
+        let path = Path::new("fixtures/linux/cpu/proc_stat_without_kernel_activity");
+        let measurement = read_and_parse_proc_stat(path).unwrap();
+
+        assert_eq!(39, measurement.stat.total);
+        assert_eq!(None, measurement.kernel_activity);
+    }
+
+    #[test]
+    fn test_kernel_activity_calculate_per_minute_without_kernel_activity() {
+        let measurement1 = CpuMeasurement {
+            precise_time_ns: 60_000_000_000,
+            stat: helpers::cpu_stat(1),
+            kernel_activity: Some(helpers::kernel_activity()),
+        };
+        let measurement2 = CpuMeasurement {
+            precise_time_ns: 120_000_000_000,
+            stat: helpers::cpu_stat(2),
+            kernel_activity: None,
+        };
+
+        match measurement1.calculate_kernel_activity_per_minute(&measurement2) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_kernel_activity_calculate_per_minute_integration() {
+        let mut measurement1 =
+            read_and_parse_proc_stat(Path::new("fixtures/linux/cpu/proc_stat_1")).unwrap();
+        measurement1.precise_time_ns = 60_000_000_000;
+        let mut measurement2 =
+            read_and_parse_proc_stat(Path::new("fixtures/linux/cpu/proc_stat_2")).unwrap();
+        measurement2.precise_time_ns = 90_000_000_000;
+
+        let expected = KernelActivity {
+            context_switches: 221912,
+            interrupts: 127326,
+            forks: 2108,
+            procs_running: 1,
+            procs_blocked: 0,
+            boot_time: 1462093485,
+        };
+        assert_eq!(
+            measurement1
+                .calculate_kernel_activity_per_minute(&measurement2)
+                .unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_kernel_activity_calculate_per_minute_values_lower() {
+        let measurement1 = CpuMeasurement {
+            precise_time_ns: 60_000_000_000,
+            stat: helpers::cpu_stat(1),
+            kernel_activity: Some(KernelActivity {
+                context_switches: 2000,
+                interrupts: 2000,
+                forks: 2000,
+                procs_running: 1,
+                procs_blocked: 0,
+                boot_time: 1462093485,
+            }),
+        };
+        let measurement2 = CpuMeasurement {
+            precise_time_ns: 120_000_000_000,
+            stat: helpers::cpu_stat(2),
+            kernel_activity: Some(KernelActivity {
+                context_switches: 1000,
+                interrupts: 1000,
+                forks: 1000,
+                procs_running: 1,
+                procs_blocked: 0,
+                boot_time: 1462093485,
+            }),
+        };
+
+        match measurement1.calculate_kernel_activity_per_minute(&measurement2) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    mod helpers {
+        use super::super::{CpuStat, KernelActivity};
+
+        pub fn kernel_activity() -> KernelActivity {
+            KernelActivity {
+                context_switches: 0,
+                interrupts: 0,
+                forks: 0,
+                procs_running: 0,
+                procs_blocked: 0,
+                boot_time: 0,
+            }
+        }
+
+        pub fn cpu_stat(value: u64) -> CpuStat {
+            CpuStat {
+                total: value * 10,
+                user: value,
+                nice: value,
+                system: value,
+                idle: value,
+                iowait: value,
+                irq: value,
+                softirq: value,
+                steal: value,
+                guest: value,
+                guestnice: value,
+            }
+        }
+    }
 }
@@ -0,0 +1,581 @@
+use crate::{calculate_time_difference, time_adjusted, Result};
+use std::collections::HashMap;
+
+/// Measurement of the cpu stats of a process at a certain time
+#[derive(Debug, PartialEq)]
+pub struct ProcessCpuMeasurement {
+    pub precise_time_ns: u64,
+    pub stat: ProcessCpuStat,
+}
+
+impl ProcessCpuMeasurement {
+    /// Calculate the cpu stats based on this measurement and a measurement in the future.
+    /// It is advisable to make the next measurement roughly a minute from this one for the
+    /// most reliable result.
+    pub fn calculate_per_minute(
+        &self,
+        next_measurement: &ProcessCpuMeasurement,
+    ) -> Result<ProcessCpuStat> {
+        let time_difference =
+            calculate_time_difference(self.precise_time_ns, next_measurement.precise_time_ns)?;
+
+        Ok(ProcessCpuStat {
+            utime: time_adjusted(
+                "utime",
+                next_measurement.stat.utime,
+                self.stat.utime,
+                time_difference,
+            )?,
+            stime: time_adjusted(
+                "stime",
+                next_measurement.stat.stime,
+                self.stat.stime,
+                time_difference,
+            )?,
+            cutime: time_adjusted(
+                "cutime",
+                next_measurement.stat.cutime,
+                self.stat.cutime,
+                time_difference,
+            )?,
+            cstime: time_adjusted(
+                "cstime",
+                next_measurement.stat.cstime,
+                self.stat.cstime,
+                time_difference,
+            )?,
+        })
+    }
+}
+
+/// Measurement of the cpu stats of a process and its descendants at a certain time. The stats
+/// are kept per process, keyed by pid and start time, so processes that start or exit between
+/// two measurements don't distort the result.
+#[derive(Debug, PartialEq)]
+pub struct ProcessTreeCpuMeasurement {
+    pub precise_time_ns: u64,
+    pub processes: HashMap<(libc::pid_t, u64), ProcessCpuStat>,
+}
+
+impl ProcessTreeCpuMeasurement {
+    /// Calculate the combined cpu stats of the processes in the tree based on this measurement
+    /// and a measurement in the future. Only processes present in both measurements are
+    /// included. The time of waited-for children is not included, since children that are
+    /// waited for are part of the tree themselves, `cutime` and `cstime` are always 0.
+    pub fn calculate_per_minute(
+        &self,
+        next_measurement: &ProcessTreeCpuMeasurement,
+    ) -> Result<ProcessCpuStat> {
+        let time_difference =
+            calculate_time_difference(self.precise_time_ns, next_measurement.precise_time_ns)?;
+
+        let mut stat = ProcessCpuStat {
+            utime: 0,
+            stime: 0,
+            cutime: 0,
+            cstime: 0,
+        };
+        for (key, process_stat) in self.processes.iter() {
+            let next_process_stat = match next_measurement.processes.get(key) {
+                Some(next_process_stat) => next_process_stat,
+                None => continue,
+            };
+
+            stat.utime += time_adjusted(
+                "utime",
+                next_process_stat.utime,
+                process_stat.utime,
+                time_difference,
+            )?;
+            stat.stime += time_adjusted(
+                "stime",
+                next_process_stat.stime,
+                process_stat.stime,
+                time_difference,
+            )?;
+        }
+
+        Ok(stat)
+    }
+}
+
+/// Process CPU stats in nanoseconds
+#[derive(Debug, PartialEq)]
+pub struct ProcessCpuStat {
+    /// Time spent in user mode
+    pub utime: u64,
+    /// Time spent in kernel mode
+    pub stime: u64,
+    /// Time waited-for children spent in user mode
+    pub cutime: u64,
+    /// Time waited-for children spent in kernel mode
+    pub cstime: u64,
+}
+
+impl ProcessCpuStat {
+    /// Calculate the weight of the various components in percentages of a single CPU
+    pub fn in_percentages(&self) -> ProcessCpuStatPercentages {
+        ProcessCpuStatPercentages {
+            total_usage: self.percentage_of_total(self.utime + self.stime),
+            utime: self.percentage_of_total(self.utime),
+            stime: self.percentage_of_total(self.stime),
+            cutime: self.percentage_of_total(self.cutime),
+            cstime: self.percentage_of_total(self.cstime),
+        }
+    }
+
+    // Divide the values by the number of (potentially fractional) CPUs allocated to the process,
+    // such as the cgroup CPU count.
+    pub fn by_cpu_count(&self, cpu_count: Option<f64>) -> ProcessCpuStat {
+        let cpu_count = cpu_count.filter(|count| *count != 0.0).unwrap_or(1.0);
+
+        ProcessCpuStat {
+            utime: (self.utime as f64 / cpu_count).round() as u64,
+            stime: (self.stime as f64 / cpu_count).round() as u64,
+            cutime: (self.cutime as f64 / cpu_count).round() as u64,
+            cstime: (self.cstime as f64 / cpu_count).round() as u64,
+        }
+    }
+
+    fn percentage_of_total(&self, value: u64) -> f32 {
+        // 60_000_000_000 being the total value. This is 60 seconds expressed in nanoseconds.
+        (value as f32 / 60_000_000_000.0) * 100.0
+    }
+}
+
+/// Process CPU stats converted to percentages
+#[derive(Debug, PartialEq)]
+pub struct ProcessCpuStatPercentages {
+    /// Time spent by the process itself in user and kernel mode
+    pub total_usage: f32,
+    pub utime: f32,
+    pub stime: f32,
+    pub cutime: f32,
+    pub cstime: f32,
+}
+
+/// Read the current CPU stats of this process.
+#[cfg(target_os = "linux")]
+pub fn read_self() -> Result<ProcessCpuMeasurement> {
+    os::read_self()
+}
+
+/// Read the current CPU stats of a process with given pid.
+#[cfg(target_os = "linux")]
+pub fn read_of(pid: libc::pid_t) -> Result<ProcessCpuMeasurement> {
+    os::read_of(pid)
+}
+
+/// Read the current CPU stats of a process with given pid and all its descendants. Descendants
+/// that exit while reading are skipped.
+#[cfg(target_os = "linux")]
+pub fn read_tree_of(pid: libc::pid_t) -> Result<ProcessTreeCpuMeasurement> {
+    os::read_tree_of(pid)
+}
+
+#[cfg(target_os = "linux")]
+mod os {
+    use super::super::super::processes::{self, os::split_proc_pid_stat};
+    use super::super::super::{
+        file_to_string, parse_u64, precise_time_ns, ticks_to_ns, ProbeError, Result,
+    };
+    use super::{ProcessCpuMeasurement, ProcessCpuStat, ProcessTreeCpuMeasurement};
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    #[inline]
+    pub fn read_self() -> Result<ProcessCpuMeasurement> {
+        read_and_parse_proc_pid_stat(Path::new("/proc/self/stat"))
+    }
+
+    #[inline]
+    pub fn read_of(pid: libc::pid_t) -> Result<ProcessCpuMeasurement> {
+        read_and_parse_proc_pid_stat(Path::new(&format!("/proc/{}/stat", pid)))
+    }
+
+    pub fn read_tree_of(pid: libc::pid_t) -> Result<ProcessTreeCpuMeasurement> {
+        let descendants = processes::read_descendants_of(pid)?;
+        read_and_parse_tree(Path::new("/proc"), pid, &descendants)
+    }
+
+    pub fn read_and_parse_tree(
+        proc_dir: &Path,
+        pid: libc::pid_t,
+        descendants: &[libc::pid_t],
+    ) -> Result<ProcessTreeCpuMeasurement> {
+        let precise_time_ns = precise_time_ns();
+
+        let mut processes = HashMap::new();
+        let (start_time, stat) = read_and_parse_process(proc_dir, pid)?;
+        processes.insert((pid, start_time), stat);
+        for descendant in descendants {
+            match read_and_parse_process(proc_dir, *descendant) {
+                Ok((start_time, stat)) => {
+                    processes.insert((*descendant, start_time), stat);
+                }
+                Err(ProbeError::IO(ref e, _)) if processes::os::is_exited(e) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(ProcessTreeCpuMeasurement {
+            precise_time_ns,
+            processes,
+        })
+    }
+
+    // The start time is kept in clock ticks, it's only used to tell processes with the same pid
+    // apart.
+    fn read_and_parse_process(proc_dir: &Path, pid: libc::pid_t) -> Result<(u64, ProcessCpuStat)> {
+        let raw_data = file_to_string(&proc_dir.join(pid.to_string()).join("stat"))?;
+        let (_, fields) = split_proc_pid_stat(&raw_data)?;
+        if fields.len() < 20 {
+            return Err(ProbeError::UnexpectedContent(
+                "Incorrect number of segments".to_owned(),
+            ));
+        }
+
+        Ok((parse_u64(fields[19])?, parse_stat(&fields)?))
+    }
+
+    pub fn read_and_parse_proc_pid_stat(path: &Path) -> Result<ProcessCpuMeasurement> {
+        let precise_time_ns = precise_time_ns();
+        let raw_data = file_to_string(path)?;
+        let (_, fields) = split_proc_pid_stat(&raw_data)?;
+        if fields.len() < 15 {
+            return Err(ProbeError::UnexpectedContent(
+                "Incorrect number of segments".to_owned(),
+            ));
+        }
+
+        Ok(ProcessCpuMeasurement {
+            precise_time_ns,
+            stat: parse_stat(&fields)?,
+        })
+    }
+
+    fn parse_stat(fields: &[&str]) -> Result<ProcessCpuStat> {
+        // Values are in clock ticks, convert them to nanoseconds.
+        Ok(ProcessCpuStat {
+            utime: ticks_to_ns(parse_u64(fields[11])?),
+            stime: ticks_to_ns(parse_u64(fields[12])?),
+            cutime: ticks_to_ns(parse_u64(fields[13])?),
+            cstime: ticks_to_ns(parse_u64(fields[14])?),
+        })
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod test {
+    use super::os::{read_and_parse_proc_pid_stat, read_and_parse_tree};
+    use super::{ProcessCpuMeasurement, ProcessCpuStat};
+    use crate::error::ProbeError;
+    use std::path::Path;
+
+    #[test]
+    fn test_read_self() {
+        assert!(super::read_self().is_ok());
+    }
+
+    #[test]
+    fn test_read_of() {
+        let pid = unsafe { libc::getpid() };
+        assert!(super::read_of(pid).is_ok());
+    }
+
+    #[test]
+    fn test_read_of_invalid_pid() {
+        match super::read_of(0) {
+            Err(ProbeError::IO(_, _)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_tree_of() {
+        let pid = unsafe { libc::getpid() };
+        let tree_measurement = super::read_tree_of(pid).unwrap();
+        assert!(!tree_measurement.processes.is_empty());
+    }
+
+    #[test]
+    fn test_read_tree_of_invalid_pid() {
+        assert!(super::read_tree_of(0).is_err());
+    }
+
+    #[test]
+    fn test_read_and_parse_tree() {
+        let path = Path::new("fixtures/linux/process_tree/proc_1");
+        let measurement = read_and_parse_tree(path, 2741, &[2750, 2751, 2752]).unwrap();
+
+        // Pid 2752 exited while reading and is skipped
+        assert_eq!(3, measurement.processes.len());
+        let expected = ProcessCpuStat {
+            utime: 5_000_000_000,
+            stime: 500_000_000,
+            cutime: 0,
+            cstime: 0,
+        };
+        assert_eq!(Some(&expected), measurement.processes.get(&(2750, 1100)));
+    }
+
+    #[test]
+    fn test_read_and_parse_tree_wrong_path() {
+        match read_and_parse_tree(Path::new("/nonsense"), 2741, &[]) {
+            Err(ProbeError::IO(_, _)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_tree_calculate_per_minute_child_exited() {
+        let mut measurement1 = read_and_parse_tree(
+            Path::new("fixtures/linux/process_tree/proc_1"),
+            2741,
+            &[2750, 2751],
+        )
+        .unwrap();
+        measurement1.precise_time_ns = 60_000_000_000;
+        // Pid 2751 exited and was waited for by 2741, pid 2760 was started
+        let mut measurement2 = read_and_parse_tree(
+            Path::new("fixtures/linux/process_tree/proc_2"),
+            2741,
+            &[2750, 2760],
+        )
+        .unwrap();
+        measurement2.precise_time_ns = 120_000_000_000;
+
+        // Only the time of 2741 and 2750, the only processes present in both measurements
+        let expected = ProcessCpuStat {
+            utime: 3_100_000_000,
+            stime: 320_000_000,
+            cutime: 0,
+            cstime: 0,
+        };
+        assert_eq!(
+            expected,
+            measurement1.calculate_per_minute(&measurement2).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_tree_calculate_per_minute_wrong_times() {
+        let mut measurement1 =
+            read_and_parse_tree(Path::new("fixtures/linux/process_tree/proc_1"), 2741, &[])
+                .unwrap();
+        measurement1.precise_time_ns = 120_000_000_000;
+        let mut measurement2 =
+            read_and_parse_tree(Path::new("fixtures/linux/process_tree/proc_2"), 2741, &[])
+                .unwrap();
+        measurement2.precise_time_ns = 60_000_000_000;
+
+        match measurement1.calculate_per_minute(&measurement2) {
+            Err(ProbeError::InvalidInput(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_pid_stat() {
+        let path = Path::new("fixtures/linux/cpu/proc_pid_stat");
+        let measurement = read_and_parse_proc_pid_stat(path).unwrap();
+
+        // Fixture values are in clock ticks, which are a hundredth of a second.
+        let expected = ProcessCpuStat {
+            utime: 15_430_000_000,
+            stime: 2_170_000_000,
+            cutime: 120_000_000,
+            cstime: 30_000_000,
+        };
+        assert_eq!(expected, measurement.stat);
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_pid_stat_wrong_path() {
+        match read_and_parse_proc_pid_stat(Path::new("/nonsense")) {
+            Err(ProbeError::IO(_, _)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_pid_stat_incomplete() {
+        let path = Path::new("fixtures/linux/cpu/proc_pid_stat_incomplete");
+        match read_and_parse_proc_pid_stat(path) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_pid_stat_garbage() {
+        let path = Path::new("fixtures/linux/cpu/proc_pid_stat_garbage");
+        match read_and_parse_proc_pid_stat(path) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_calculate_per_minute_full_minute() {
+        let measurement1 = ProcessCpuMeasurement {
+            precise_time_ns: 60_000_000_000,
+            stat: ProcessCpuStat {
+                utime: 1_000_000_000,
+                stime: 1_000_000_000,
+                cutime: 0,
+                cstime: 0,
+            },
+        };
+        let measurement2 = ProcessCpuMeasurement {
+            precise_time_ns: 120_000_000_000,
+            stat: ProcessCpuStat {
+                utime: 13_000_000_000,
+                stime: 4_000_000_000,
+                cutime: 600_000_000,
+                cstime: 0,
+            },
+        };
+
+        let expected = ProcessCpuStat {
+            utime: 12_000_000_000,
+            stime: 3_000_000_000,
+            cutime: 600_000_000,
+            cstime: 0,
+        };
+        assert_eq!(
+            expected,
+            measurement1.calculate_per_minute(&measurement2).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_calculate_per_minute_partial_minute() {
+        let measurement1 = ProcessCpuMeasurement {
+            precise_time_ns: 60_000_000_000,
+            stat: ProcessCpuStat {
+                utime: 1_000_000_000,
+                stime: 1_000_000_000,
+                cutime: 0,
+                cstime: 0,
+            },
+        };
+        let measurement2 = ProcessCpuMeasurement {
+            precise_time_ns: 90_000_000_000,
+            stat: ProcessCpuStat {
+                utime: 7_000_000_000,
+                stime: 2_500_000_000,
+                cutime: 0,
+                cstime: 0,
+            },
+        };
+
+        let expected = ProcessCpuStat {
+            utime: 12_000_000_000,
+            stime: 3_000_000_000,
+            cutime: 0,
+            cstime: 0,
+        };
+        assert_eq!(
+            expected,
+            measurement1.calculate_per_minute(&measurement2).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_calculate_per_minute_wrong_times() {
+        let measurement1 = ProcessCpuMeasurement {
+            precise_time_ns: 90_000_000_000,
+            stat: ProcessCpuStat {
+                utime: 0,
+                stime: 0,
+                cutime: 0,
+                cstime: 0,
+            },
+        };
+        let measurement2 = ProcessCpuMeasurement {
+            precise_time_ns: 60_000_000_000,
+            stat: ProcessCpuStat {
+                utime: 0,
+                stime: 0,
+                cutime: 0,
+                cstime: 0,
+            },
+        };
+
+        match measurement1.calculate_per_minute(&measurement2) {
+            Err(ProbeError::InvalidInput(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_calculate_per_minute_values_lower() {
+        let measurement1 = ProcessCpuMeasurement {
+            precise_time_ns: 60_000_000_000,
+            stat: ProcessCpuStat {
+                utime: 1_000_000_000,
+                stime: 0,
+                cutime: 0,
+                cstime: 0,
+            },
+        };
+        let measurement2 = ProcessCpuMeasurement {
+            precise_time_ns: 90_000_000_000,
+            stat: ProcessCpuStat {
+                utime: 500_000_000,
+                stime: 0,
+                cutime: 0,
+                cstime: 0,
+            },
+        };
+
+        match measurement1.calculate_per_minute(&measurement2) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_in_percentages() {
+        let stat = ProcessCpuStat {
+            utime: 24_000_000_000,
+            stime: 6_000_000_000,
+            cutime: 3_000_000_000,
+            cstime: 0,
+        };
+
+        let in_percentages = stat.in_percentages();
+
+        assert!(in_percentages.total_usage > 49.9);
+        assert!(in_percentages.total_usage <= 50.1);
+        assert!(in_percentages.utime > 39.9);
+        assert!(in_percentages.utime <= 40.1);
+        assert!(in_percentages.stime > 9.9);
+        assert!(in_percentages.stime <= 10.1);
+        assert!(in_percentages.cutime > 4.9);
+        assert!(in_percentages.cutime <= 5.1);
+        assert_eq!(0.0, in_percentages.cstime);
+    }
+
+    #[test]
+    fn test_by_cpu_count() {
+        let stat = ProcessCpuStat {
+            utime: 24_000_000_000,
+            stime: 6_000_000_000,
+            cutime: 3_000_000_000,
+            cstime: 0,
+        };
+
+        let expected = ProcessCpuStat {
+            utime: 12_000_000_000,
+            stime: 3_000_000_000,
+            cutime: 1_500_000_000,
+            cstime: 0,
+        };
+        assert_eq!(expected, stat.by_cpu_count(Some(2.0)));
+        assert_eq!(stat, stat.by_cpu_count(None));
+        assert_eq!(stat, stat.by_cpu_count(Some(0.0)));
+    }
+}
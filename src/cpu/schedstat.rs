@@ -0,0 +1,488 @@
+use super::super::{calculate_time_difference, time_adjusted, Result};
+use std::collections::HashMap;
+
+/// Scheduler statistics as reported in `/proc/<pid>/schedstat` and `/proc/schedstat`
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SchedStat {
+    /// Time spent running on a CPU in nanoseconds
+    pub run_time: u64,
+    /// Time spent waiting on a run queue in nanoseconds
+    pub wait_time: u64,
+    /// Number of timeslices run
+    pub timeslices: u64,
+}
+
+impl SchedStat {
+    fn per_minute(&self, next_stat: &SchedStat, time_difference: u64) -> Result<SchedStat> {
+        Ok(SchedStat {
+            run_time: time_adjusted(
+                "run_time",
+                next_stat.run_time,
+                self.run_time,
+                time_difference,
+            )?,
+            wait_time: time_adjusted(
+                "wait_time",
+                next_stat.wait_time,
+                self.wait_time,
+                time_difference,
+            )?,
+            timeslices: time_adjusted(
+                "timeslices",
+                next_stat.timeslices,
+                self.timeslices,
+                time_difference,
+            )?,
+        })
+    }
+
+    /// Average time waited on a run queue per timeslice in nanoseconds, `None` when no
+    /// timeslices were run.
+    pub fn average_wait_time(&self) -> Option<u64> {
+        if self.timeslices == 0 {
+            return None;
+        }
+        Some(self.wait_time / self.timeslices)
+    }
+
+    /// Time waited on a run queue in percentages of a minute
+    pub fn wait_percentage(&self) -> f32 {
+        // 60_000_000_000 being the total value. This is 60 seconds expressed in nanoseconds.
+        (self.wait_time as f32 / 60_000_000_000.0) * 100.0
+    }
+}
+
+/// Measurement of the scheduler stats of a process at a certain time
+#[derive(Debug, PartialEq)]
+pub struct ProcessSchedStatMeasurement {
+    pub precise_time_ns: u64,
+    pub stat: SchedStat,
+}
+
+impl ProcessSchedStatMeasurement {
+    /// Calculate the scheduler stats per minute based on this measurement and a measurement in
+    /// the future. It is advisable to make the next measurement roughly a minute from this one for
+    /// the most reliable result.
+    pub fn calculate_per_minute(
+        &self,
+        next_measurement: &ProcessSchedStatMeasurement,
+    ) -> Result<SchedStat> {
+        let time_difference =
+            calculate_time_difference(self.precise_time_ns, next_measurement.precise_time_ns)?;
+
+        self.stat
+            .per_minute(&next_measurement.stat, time_difference)
+    }
+}
+
+pub type SchedStatCpus = HashMap<usize, SchedStat>;
+
+/// Measurement of the per-CPU scheduler stats of the system at a certain time
+#[derive(Debug, PartialEq)]
+pub struct SchedStatMeasurement {
+    pub precise_time_ns: u64,
+    pub cpus: SchedStatCpus,
+}
+
+impl SchedStatMeasurement {
+    /// Calculate the scheduler stats per CPU based on this measurement and a measurement in the
+    /// future. CPUs that are not present in both measurements, because they went offline or came
+    /// online in between, are left out of the result.
+    pub fn calculate_per_minute(
+        &self,
+        next_measurement: &SchedStatMeasurement,
+    ) -> Result<PerCpuSchedStat> {
+        let time_difference =
+            calculate_time_difference(self.precise_time_ns, next_measurement.precise_time_ns)?;
+
+        let mut cpus = SchedStatCpus::new();
+
+        for (index, stat) in self.cpus.iter() {
+            if let Some(next_stat) = next_measurement.cpus.get(index) {
+                cpus.insert(*index, stat.per_minute(next_stat, time_difference)?);
+            }
+        }
+
+        Ok(PerCpuSchedStat { cpus })
+    }
+}
+
+/// Scheduler stats per CPU for a minute
+#[derive(Debug, PartialEq)]
+pub struct PerCpuSchedStat {
+    pub cpus: SchedStatCpus,
+}
+
+impl PerCpuSchedStat {
+    /// Scheduler stats of all CPUs combined
+    pub fn total(&self) -> SchedStat {
+        self.cpus.values().fold(
+            SchedStat {
+                run_time: 0,
+                wait_time: 0,
+                timeslices: 0,
+            },
+            |total, stat| SchedStat {
+                run_time: total.run_time + stat.run_time,
+                wait_time: total.wait_time + stat.wait_time,
+                timeslices: total.timeslices + stat.timeslices,
+            },
+        )
+    }
+}
+
+/// Read the scheduler stats of this process.
+#[cfg(target_os = "linux")]
+pub fn read_self() -> Result<ProcessSchedStatMeasurement> {
+    os::read_self()
+}
+
+/// Read the scheduler stats of a process with given pid.
+#[cfg(target_os = "linux")]
+pub fn read_of(pid: libc::pid_t) -> Result<ProcessSchedStatMeasurement> {
+    os::read_of(pid)
+}
+
+/// Read the per-CPU scheduler stats of the system. Requires a kernel with `CONFIG_SCHEDSTATS`.
+#[cfg(target_os = "linux")]
+pub fn read() -> Result<SchedStatMeasurement> {
+    os::read()
+}
+
+#[cfg(target_os = "linux")]
+mod os {
+    use super::super::super::{
+        file_to_buf_reader, file_to_string, parse_u64, path_to_string, precise_time_ns, ProbeError,
+        Result,
+    };
+    use super::{ProcessSchedStatMeasurement, SchedStat, SchedStatCpus, SchedStatMeasurement};
+    use std::io::BufRead;
+    use std::path::Path;
+
+    const PROC_SCHEDSTAT_CPU_NUMBER_OF_FIELDS: usize = 9;
+
+    #[inline]
+    pub fn read_self() -> Result<ProcessSchedStatMeasurement> {
+        read_and_parse_proc_pid_schedstat(Path::new("/proc/self/schedstat"))
+    }
+
+    #[inline]
+    pub fn read_of(pid: libc::pid_t) -> Result<ProcessSchedStatMeasurement> {
+        read_and_parse_proc_pid_schedstat(Path::new(&format!("/proc/{}/schedstat", pid)))
+    }
+
+    #[inline]
+    pub fn read() -> Result<SchedStatMeasurement> {
+        read_and_parse_proc_schedstat(Path::new("/proc/schedstat"))
+    }
+
+    // columns: run_time wait_time timeslices
+    pub fn read_and_parse_proc_pid_schedstat(path: &Path) -> Result<ProcessSchedStatMeasurement> {
+        let precise_time_ns = precise_time_ns();
+        let raw_data = file_to_string(path)?;
+        let segments: Vec<&str> = raw_data.split_whitespace().collect();
+
+        if segments.len() != 3 {
+            return Err(ProbeError::UnexpectedContent(
+                "Incorrect number of segments".to_owned(),
+            ));
+        }
+
+        Ok(ProcessSchedStatMeasurement {
+            precise_time_ns,
+            stat: SchedStat {
+                run_time: parse_u64(segments[0])?,
+                wait_time: parse_u64(segments[1])?,
+                timeslices: parse_u64(segments[2])?,
+            },
+        })
+    }
+
+    // columns: cpuN yld_count 0 sched_count sched_goidle ttwu_count ttwu_local run_time
+    //          wait_time timeslices
+    // Newer versions of the format can append columns, those are ignored.
+    pub fn read_and_parse_proc_schedstat(path: &Path) -> Result<SchedStatMeasurement> {
+        let reader = file_to_buf_reader(path)?;
+        let precise_time_ns = precise_time_ns();
+
+        let mut cpus = SchedStatCpus::new();
+        for line_result in reader.lines() {
+            let line = line_result.map_err(|e| ProbeError::IO(e, path_to_string(path)))?;
+            let segments: Vec<&str> = line.split_whitespace().collect();
+
+            let index = match segments.first().and_then(|name| name.strip_prefix("cpu")) {
+                Some(index) => index.parse::<usize>().map_err(|_| {
+                    ProbeError::UnexpectedContent(format!("Could not parse '{}' as cpu", index))
+                })?,
+                _ => continue,
+            };
+
+            if segments.len() < PROC_SCHEDSTAT_CPU_NUMBER_OF_FIELDS + 1 {
+                return Err(ProbeError::UnexpectedContent(
+                    "Incorrect number of segments".to_owned(),
+                ));
+            }
+
+            cpus.insert(
+                index,
+                SchedStat {
+                    run_time: parse_u64(segments[7])?,
+                    wait_time: parse_u64(segments[8])?,
+                    timeslices: parse_u64(segments[9])?,
+                },
+            );
+        }
+
+        if cpus.is_empty() {
+            return Err(ProbeError::UnexpectedContent(
+                "No per-cpu scheduler stats found".to_owned(),
+            ));
+        }
+
+        Ok(SchedStatMeasurement {
+            precise_time_ns,
+            cpus,
+        })
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+    use super::super::super::ProbeError;
+    use super::os::{read_and_parse_proc_pid_schedstat, read_and_parse_proc_schedstat};
+    use super::{
+        PerCpuSchedStat, ProcessSchedStatMeasurement, SchedStat, SchedStatCpus,
+        SchedStatMeasurement,
+    };
+    use std::path::Path;
+
+    #[test]
+    fn test_read_self() {
+        assert!(super::read_self().is_ok());
+    }
+
+    #[test]
+    fn test_read_of() {
+        let pid = unsafe { libc::getpid() };
+        assert!(super::read_of(pid).is_ok());
+    }
+
+    #[test]
+    fn test_read_of_invalid_pid() {
+        assert!(super::read_of(0).is_err());
+    }
+
+    #[test]
+    fn test_read() {
+        // Only available on kernels with CONFIG_SCHEDSTATS
+        if Path::new("/proc/schedstat").exists() {
+            assert!(!super::read().unwrap().cpus.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_pid_schedstat() {
+        let path = Path::new("fixtures/linux/schedstat/proc_pid_schedstat");
+        let measurement = read_and_parse_proc_pid_schedstat(path).unwrap();
+
+        let expected = SchedStat {
+            run_time: 1_463_782_519,
+            wait_time: 20_611_354,
+            timeslices: 1_022,
+        };
+        assert_eq!(expected, measurement.stat);
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_pid_schedstat_wrong_path() {
+        match read_and_parse_proc_pid_schedstat(Path::new("/nonsense")) {
+            Err(ProbeError::IO(_, _)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_pid_schedstat_incomplete() {
+        let path = Path::new("fixtures/linux/schedstat/proc_pid_schedstat_incomplete");
+        match read_and_parse_proc_pid_schedstat(path) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_pid_schedstat_garbage() {
+        let path = Path::new("fixtures/linux/schedstat/proc_pid_schedstat_garbage");
+        match read_and_parse_proc_pid_schedstat(path) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_schedstat() {
+        let path = Path::new("fixtures/linux/schedstat/proc_schedstat");
+        let measurement = read_and_parse_proc_schedstat(path).unwrap();
+
+        assert_eq!(2, measurement.cpus.len());
+        assert_eq!(
+            &SchedStat {
+                run_time: 1_461_233_120_011,
+                wait_time: 163_567_020_345,
+                timeslices: 3_502_110,
+            },
+            measurement.cpus.get(&0).unwrap()
+        );
+        assert_eq!(
+            &SchedStat {
+                run_time: 1_398_102_449_873,
+                wait_time: 171_002_118_004,
+                timeslices: 3_387_901,
+            },
+            measurement.cpus.get(&1).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_schedstat_appended_fields() {
+        let path = Path::new("fixtures/linux/schedstat/proc_schedstat_appended_fields");
+        let measurement = read_and_parse_proc_schedstat(path).unwrap();
+
+        assert_eq!(2, measurement.cpus.len());
+        assert_eq!(
+            &SchedStat {
+                run_time: 1_398_102_449_873,
+                wait_time: 171_002_118_004,
+                timeslices: 3_387_901,
+            },
+            measurement.cpus.get(&1).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_schedstat_wrong_path() {
+        match read_and_parse_proc_schedstat(Path::new("/nonsense")) {
+            Err(ProbeError::IO(_, _)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_schedstat_incomplete() {
+        let path = Path::new("fixtures/linux/schedstat/proc_schedstat_incomplete");
+        match read_and_parse_proc_schedstat(path) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_schedstat_garbage() {
+        let path = Path::new("fixtures/linux/schedstat/proc_schedstat_garbage");
+        match read_and_parse_proc_schedstat(path) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_calculate_per_minute_partial_minute() {
+        let measurement1 = ProcessSchedStatMeasurement {
+            precise_time_ns: 60_000_000_000,
+            stat: helpers::sched_stat(1_000_000_000, 100),
+        };
+        let measurement2 = ProcessSchedStatMeasurement {
+            precise_time_ns: 90_000_000_000,
+            stat: helpers::sched_stat(4_000_000_000, 400),
+        };
+
+        let per_minute = measurement1.calculate_per_minute(&measurement2).unwrap();
+        assert_eq!(helpers::sched_stat(6_000_000_000, 600), per_minute);
+        assert_eq!(Some(10_000_000), per_minute.average_wait_time());
+        assert!(per_minute.wait_percentage() > 9.9);
+        assert!(per_minute.wait_percentage() <= 10.0);
+    }
+
+    #[test]
+    fn test_calculate_per_minute_wrong_times() {
+        let measurement1 = ProcessSchedStatMeasurement {
+            precise_time_ns: 90_000_000_000,
+            stat: helpers::sched_stat(0, 0),
+        };
+        let measurement2 = ProcessSchedStatMeasurement {
+            precise_time_ns: 60_000_000_000,
+            stat: helpers::sched_stat(0, 0),
+        };
+
+        match measurement1.calculate_per_minute(&measurement2) {
+            Err(ProbeError::InvalidInput(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_calculate_per_minute_values_lower() {
+        let measurement1 = ProcessSchedStatMeasurement {
+            precise_time_ns: 60_000_000_000,
+            stat: helpers::sched_stat(1_000_000_000, 100),
+        };
+        let measurement2 = ProcessSchedStatMeasurement {
+            precise_time_ns: 90_000_000_000,
+            stat: helpers::sched_stat(500_000_000, 100),
+        };
+
+        match measurement1.calculate_per_minute(&measurement2) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_average_wait_time_without_timeslices() {
+        assert_eq!(None, helpers::sched_stat(0, 0).average_wait_time());
+    }
+
+    #[test]
+    fn test_calculate_per_minute_per_cpu() {
+        let mut cpus1 = SchedStatCpus::new();
+        cpus1.insert(0, helpers::sched_stat(1_000_000_000, 100));
+        cpus1.insert(1, helpers::sched_stat(1_000_000_000, 100));
+        cpus1.insert(2, helpers::sched_stat(1_000_000_000, 100));
+        let measurement1 = SchedStatMeasurement {
+            precise_time_ns: 60_000_000_000,
+            cpus: cpus1,
+        };
+        // Cpu 2 went offline
+        let mut cpus2 = SchedStatCpus::new();
+        cpus2.insert(0, helpers::sched_stat(2_000_000_000, 200));
+        cpus2.insert(1, helpers::sched_stat(4_000_000_000, 400));
+        let measurement2 = SchedStatMeasurement {
+            precise_time_ns: 120_000_000_000,
+            cpus: cpus2,
+        };
+
+        let mut expected_cpus = SchedStatCpus::new();
+        expected_cpus.insert(0, helpers::sched_stat(1_000_000_000, 100));
+        expected_cpus.insert(1, helpers::sched_stat(3_000_000_000, 300));
+        let expected = PerCpuSchedStat {
+            cpus: expected_cpus,
+        };
+
+        let per_minute = measurement1.calculate_per_minute(&measurement2).unwrap();
+        assert_eq!(expected, per_minute);
+        assert_eq!(helpers::sched_stat(4_000_000_000, 400), per_minute.total());
+    }
+
+    mod helpers {
+        use super::super::SchedStat;
+
+        pub fn sched_stat(time: u64, timeslices: u64) -> SchedStat {
+            SchedStat {
+                run_time: time,
+                wait_time: time,
+                timeslices,
+            }
+        }
+    }
+}
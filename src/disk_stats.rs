@@ -1,5 +1,4 @@
 use super::{calculate_time_difference, time_adjusted, Result};
-use crate::error::ProbeError;
 use std::collections::HashMap;
 use std::path::Path;
 
@@ -14,7 +13,9 @@ pub struct DiskStatsMeasurement {
 impl DiskStatsMeasurement {
     /// Calculate the disk stats per minute based on this measurement and a measurement in the
     /// future. It is advisable to make the next measurement roughly a minute from this one for the
-    /// most reliable result.
+    /// most reliable result. Disks that are not present in both measurements are listed as added
+    /// or removed, disks with counters that went down are listed as reset. These are left out of
+    /// the result.
     pub fn calculate_per_minute(
         &self,
         next_measurement: &DiskStatsMeasurement,
@@ -23,92 +24,45 @@ impl DiskStatsMeasurement {
             calculate_time_difference(self.precise_time_ns, next_measurement.precise_time_ns)?;
 
         let mut stats = HashMap::new();
+        let mut removed = Vec::new();
+        let mut reset = Vec::new();
 
         for (name, stat) in self.stats.iter() {
             let next_stat = match next_measurement.stats.get(name) {
                 Some(stat) => stat,
                 None => {
-                    return Err(ProbeError::UnexpectedContent(format!(
-                        "{} is not present in the next measurement",
-                        name
-                    )))
+                    removed.push(name.to_owned());
+                    continue;
                 }
             };
+            if stat.is_reset(next_stat) {
+                reset.push(name.to_owned());
+                continue;
+            }
 
             stats.insert(
                 name.to_owned(),
-                DiskStat {
-                    reads_completed_successfully: time_adjusted(
-                        "reads_completed_successfully",
-                        next_stat.reads_completed_successfully,
-                        stat.reads_completed_successfully,
-                        time_difference,
-                    )?,
-                    reads_merged: time_adjusted(
-                        "reads_merged",
-                        next_stat.reads_merged,
-                        stat.reads_merged,
-                        time_difference,
-                    )?,
-                    sectors_read: time_adjusted(
-                        "sectors_read",
-                        next_stat.sectors_read,
-                        stat.sectors_read,
-                        time_difference,
-                    )?,
-                    time_spent_reading_ms: time_adjusted(
-                        "time_spent_reading_ms",
-                        next_stat.time_spent_reading_ms,
-                        stat.time_spent_reading_ms,
-                        time_difference,
-                    )?,
-                    writes_completed: time_adjusted(
-                        "writes_completed",
-                        next_stat.writes_completed,
-                        stat.writes_completed,
-                        time_difference,
-                    )?,
-                    writes_merged: time_adjusted(
-                        "writes_merged",
-                        next_stat.writes_merged,
-                        stat.writes_merged,
-                        time_difference,
-                    )?,
-                    sectors_written: time_adjusted(
-                        "sectors_written",
-                        next_stat.sectors_written,
-                        stat.sectors_written,
-                        time_difference,
-                    )?,
-                    time_spent_writing_ms: time_adjusted(
-                        "time_spent_writing_ms",
-                        next_stat.time_spent_writing_ms,
-                        stat.time_spent_writing_ms,
-                        time_difference,
-                    )?,
-                    ios_currently_in_progress: time_adjusted(
-                        "ios_currently_in_progress",
-                        next_stat.ios_currently_in_progress,
-                        stat.ios_currently_in_progress,
-                        time_difference,
-                    )?,
-                    time_spent_doing_ios_ms: time_adjusted(
-                        "time_spent_doing_ios_ms",
-                        next_stat.time_spent_doing_ios_ms,
-                        stat.time_spent_doing_ios_ms,
-                        time_difference,
-                    )?,
-                    weighted_time_spent_doing_ios_ms: time_adjusted(
-                        "weighted_time_spent_doing_ios_ms",
-                        next_stat.weighted_time_spent_doing_ios_ms,
-                        stat.weighted_time_spent_doing_ios_ms,
-                        time_difference,
-                    )?,
-                },
+                stat.per_minute(next_stat, time_difference)?,
             );
         }
 
-        Ok(DiskStatsPerMinute { stats })
+        let mut added: Vec<String> = next_measurement
+            .stats
+            .keys()
+            .filter(|name| !self.stats.contains_key(*name))
+            .cloned()
+            .collect();
+
+        added.sort();
+        removed.sort();
+        reset.sort();
+
+        Ok(DiskStatsPerMinute {
+            stats,
+            added,
+            removed,
+            reset,
+        })
     }
 }
 
@@ -122,12 +76,102 @@ pub struct DiskStat {
     pub writes_merged: u64,
     pub sectors_written: u64,
     pub time_spent_writing_ms: u64,
+    /// Number of I/Os in progress at the time of the measurement, this is not a counter
     pub ios_currently_in_progress: u64,
     pub time_spent_doing_ios_ms: u64,
     pub weighted_time_spent_doing_ios_ms: u64,
 }
 
 impl DiskStat {
+    fn per_minute(&self, next_stat: &DiskStat, time_difference: u64) -> Result<DiskStat> {
+        Ok(DiskStat {
+            reads_completed_successfully: time_adjusted(
+                "reads_completed_successfully",
+                next_stat.reads_completed_successfully,
+                self.reads_completed_successfully,
+                time_difference,
+            )?,
+            reads_merged: time_adjusted(
+                "reads_merged",
+                next_stat.reads_merged,
+                self.reads_merged,
+                time_difference,
+            )?,
+            sectors_read: time_adjusted(
+                "sectors_read",
+                next_stat.sectors_read,
+                self.sectors_read,
+                time_difference,
+            )?,
+            time_spent_reading_ms: time_adjusted(
+                "time_spent_reading_ms",
+                next_stat.time_spent_reading_ms,
+                self.time_spent_reading_ms,
+                time_difference,
+            )?,
+            writes_completed: time_adjusted(
+                "writes_completed",
+                next_stat.writes_completed,
+                self.writes_completed,
+                time_difference,
+            )?,
+            writes_merged: time_adjusted(
+                "writes_merged",
+                next_stat.writes_merged,
+                self.writes_merged,
+                time_difference,
+            )?,
+            sectors_written: time_adjusted(
+                "sectors_written",
+                next_stat.sectors_written,
+                self.sectors_written,
+                time_difference,
+            )?,
+            time_spent_writing_ms: time_adjusted(
+                "time_spent_writing_ms",
+                next_stat.time_spent_writing_ms,
+                self.time_spent_writing_ms,
+                time_difference,
+            )?,
+            ios_currently_in_progress: next_stat.ios_currently_in_progress,
+            time_spent_doing_ios_ms: time_adjusted(
+                "time_spent_doing_ios_ms",
+                next_stat.time_spent_doing_ios_ms,
+                self.time_spent_doing_ios_ms,
+                time_difference,
+            )?,
+            weighted_time_spent_doing_ios_ms: time_adjusted(
+                "weighted_time_spent_doing_ios_ms",
+                next_stat.weighted_time_spent_doing_ios_ms,
+                self.weighted_time_spent_doing_ios_ms,
+                time_difference,
+            )?,
+        })
+    }
+
+    fn is_reset(&self, next_stat: &DiskStat) -> bool {
+        self.counters()
+            .iter()
+            .zip(next_stat.counters().iter())
+            .any(|(value, next_value)| next_value < value)
+    }
+
+    // All fields except `ios_currently_in_progress`, which is not a counter.
+    fn counters(&self) -> [u64; 10] {
+        [
+            self.reads_completed_successfully,
+            self.reads_merged,
+            self.sectors_read,
+            self.time_spent_reading_ms,
+            self.writes_completed,
+            self.writes_merged,
+            self.sectors_written,
+            self.time_spent_writing_ms,
+            self.time_spent_doing_ios_ms,
+            self.weighted_time_spent_doing_ios_ms,
+        ]
+    }
+
     pub fn bytes_read(&self) -> u64 {
         self.sectors_read * 512
     }
@@ -140,6 +184,12 @@ impl DiskStat {
 #[derive(Debug, PartialEq)]
 pub struct DiskStatsPerMinute {
     pub stats: DiskStats,
+    /// Disks only present in the next measurement
+    pub added: Vec<String>,
+    /// Disks only present in the first measurement
+    pub removed: Vec<String>,
+    /// Disks of which the counters were reset between the measurements
+    pub reset: Vec<String>,
 }
 
 #[cfg(target_os = "linux")]
@@ -415,7 +465,7 @@ mod tests {
         assert_eq!(sda1.writes_merged, 240);
         assert_eq!(sda1.sectors_written, 240);
         assert_eq!(sda1.time_spent_writing_ms, 240);
-        assert_eq!(sda1.ios_currently_in_progress, 240);
+        assert_eq!(sda1.ios_currently_in_progress, 120);
         assert_eq!(sda1.time_spent_doing_ios_ms, 240);
         assert_eq!(sda1.weighted_time_spent_doing_ios_ms, 240);
     }
@@ -438,7 +488,7 @@ mod tests {
     }
 
     #[test]
-    fn test_calculate_per_minute_values_lower() {
+    fn test_calculate_per_minute_values_lower_is_reset() {
         let mut stats1 = HashMap::new();
         stats1.insert("sda1".to_owned(), helpers::disk_stat(500));
         let measurement1 = DiskStatsMeasurement {
@@ -452,10 +502,9 @@ mod tests {
             stats: stats2,
         };
 
-        match measurement1.calculate_per_minute(&measurement2) {
-            Err(ProbeError::UnexpectedContent(_)) => (),
-            r => panic!("Unexpected result: {:?}", r),
-        }
+        let per_minute = measurement1.calculate_per_minute(&measurement2).unwrap();
+        assert!(per_minute.stats.is_empty());
+        assert_eq!(vec!["sda1".to_owned()], per_minute.reset);
     }
 
     #[test]
@@ -473,10 +522,35 @@ mod tests {
             stats: stats2,
         };
 
-        match measurement1.calculate_per_minute(&measurement2) {
-            Err(ProbeError::UnexpectedContent(_)) => (),
-            r => panic!("Unexpected result: {:?}", r),
-        }
+        let per_minute = measurement1.calculate_per_minute(&measurement2).unwrap();
+        assert!(per_minute.stats.is_empty());
+        assert_eq!(vec!["sda2".to_owned()], per_minute.added);
+        assert_eq!(vec!["sda1".to_owned()], per_minute.removed);
+        assert!(per_minute.reset.is_empty());
+    }
+
+    #[test]
+    fn test_calculate_per_minute_ios_in_progress_lower() {
+        let mut stats1 = HashMap::new();
+        stats1.insert("sda1".to_owned(), helpers::disk_stat(100));
+        let measurement1 = DiskStatsMeasurement {
+            precise_time_ns: 60_000_000_000,
+            stats: stats1,
+        };
+        let mut stat2 = helpers::disk_stat(220);
+        stat2.ios_currently_in_progress = 3;
+        let mut stats2 = HashMap::new();
+        stats2.insert("sda1".to_owned(), stat2);
+        let measurement2 = DiskStatsMeasurement {
+            precise_time_ns: 120_000_000_000,
+            stats: stats2,
+        };
+
+        let per_minute = measurement1.calculate_per_minute(&measurement2).unwrap();
+        assert!(per_minute.reset.is_empty());
+        let sda1 = per_minute.stats.get("sda1").unwrap();
+        assert_eq!(120, sda1.reads_completed_successfully);
+        assert_eq!(3, sda1.ios_currently_in_progress);
     }
 
     mod helpers {
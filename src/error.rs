@@ -13,6 +13,9 @@ pub enum ProbeError {
     InvalidInput(String),
     /// Command failed
     StatusFailure(String),
+    /// Not allowed to read the file described in the field,
+    /// such as files of processes owned by other users
+    PermissionDenied(String),
 }
 
 impl fmt::Display for ProbeError {
@@ -22,6 +25,7 @@ impl fmt::Display for ProbeError {
             ProbeError::UnexpectedContent(ref err) => write!(f, "{}", err),
             ProbeError::InvalidInput(ref err) => write!(f, "{}", err),
             ProbeError::StatusFailure(ref err) => write!(f, "{}", err),
+            ProbeError::PermissionDenied(ref path) => write!(f, "Permission denied for {}", path),
         }
     }
 }
@@ -34,6 +38,7 @@ impl error::Error for ProbeError {
             ProbeError::UnexpectedContent(ref err) => err,
             ProbeError::InvalidInput(ref err) => err,
             ProbeError::StatusFailure(ref err) => err,
+            ProbeError::PermissionDenied(ref path) => path,
         }
     }
 
@@ -43,6 +48,7 @@ impl error::Error for ProbeError {
             ProbeError::UnexpectedContent(_) => None,
             ProbeError::InvalidInput(_) => None,
             ProbeError::StatusFailure(_) => None,
+            ProbeError::PermissionDenied(_) => None,
         }
     }
 }
@@ -0,0 +1,241 @@
+use super::process_limits::Limit;
+use super::Result;
+
+/// File descriptor usage of a process
+#[derive(Debug, PartialEq)]
+pub struct ProcessFileDescriptors {
+    /// Number of open file descriptors
+    pub open: u64,
+    /// Limit on the number of open file descriptors
+    pub limit: Limit,
+}
+
+impl ProcessFileDescriptors {
+    /// Percentage of the soft limit in use, `None` when there is no limit.
+    pub fn usage_percentage(&self) -> Option<f32> {
+        self.limit.soft.map(|soft| percentage_of(self.open, soft))
+    }
+}
+
+/// File handle usage of the system from `/proc/sys/fs`
+#[derive(Debug, PartialEq)]
+pub struct SystemFileDescriptors {
+    /// Number of allocated file handles
+    pub allocated: u64,
+    /// Number of allocated but unused file handles, always 0 since Linux 2.6
+    pub unused: u64,
+    /// Maximum number of file handles the kernel will allocate
+    pub max: u64,
+}
+
+impl SystemFileDescriptors {
+    /// Percentage of the maximum number of file handles in use
+    pub fn usage_percentage(&self) -> f32 {
+        percentage_of(self.allocated.saturating_sub(self.unused), self.max)
+    }
+}
+
+fn percentage_of(value: u64, total: u64) -> f32 {
+    if total == 0 {
+        return 100.0;
+    }
+    (value as f64 / total as f64 * 100.0) as f32
+}
+
+/// Read the file descriptor usage of this process.
+#[cfg(target_os = "linux")]
+pub fn read_self() -> Result<ProcessFileDescriptors> {
+    os::read_self()
+}
+
+/// Read the file descriptor usage of a process with given pid.
+#[cfg(target_os = "linux")]
+pub fn read_of(pid: libc::pid_t) -> Result<ProcessFileDescriptors> {
+    os::read_of(pid)
+}
+
+/// Read the file handle usage of the system.
+#[cfg(target_os = "linux")]
+pub fn read_system() -> Result<SystemFileDescriptors> {
+    os::read_system()
+}
+
+#[cfg(target_os = "linux")]
+mod os {
+    use super::super::{
+        file_to_string, parse_u64, path_to_string, process_limits, read_file_value_as_u64,
+        ProbeError, Result,
+    };
+    use super::{ProcessFileDescriptors, SystemFileDescriptors};
+    use std::fs;
+    use std::path::Path;
+
+    #[inline]
+    pub fn read_self() -> Result<ProcessFileDescriptors> {
+        Ok(ProcessFileDescriptors {
+            open: read_and_count_fd_dir(Path::new("/proc/self/fd"))?,
+            limit: process_limits::read_self()?.open_files,
+        })
+    }
+
+    #[inline]
+    pub fn read_of(pid: libc::pid_t) -> Result<ProcessFileDescriptors> {
+        Ok(ProcessFileDescriptors {
+            open: read_and_count_fd_dir(Path::new(&format!("/proc/{}/fd", pid)))?,
+            limit: process_limits::read_of(pid)?.open_files,
+        })
+    }
+
+    #[inline]
+    pub fn read_system() -> Result<SystemFileDescriptors> {
+        read_and_parse_file_nr(
+            Path::new("/proc/sys/fs/file-nr"),
+            Path::new("/proc/sys/fs/file-max"),
+        )
+    }
+
+    pub fn read_and_count_fd_dir(path: &Path) -> Result<u64> {
+        let entries = fs::read_dir(path).map_err(|e| ProbeError::IO(e, path_to_string(path)))?;
+
+        let mut count = 0;
+        for entry in entries {
+            entry.map_err(|e| ProbeError::IO(e, path_to_string(path)))?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    // columns: allocated unused max
+    pub fn read_and_parse_file_nr(
+        file_nr_path: &Path,
+        file_max_path: &Path,
+    ) -> Result<SystemFileDescriptors> {
+        let raw_data = file_to_string(file_nr_path)?;
+        let segments: Vec<&str> = raw_data.split_whitespace().collect();
+
+        if segments.len() != 3 {
+            return Err(ProbeError::UnexpectedContent(
+                "Incorrect number of segments".to_owned(),
+            ));
+        }
+
+        Ok(SystemFileDescriptors {
+            allocated: parse_u64(segments[0])?,
+            unused: parse_u64(segments[1])?,
+            max: read_file_value_as_u64(file_max_path)?,
+        })
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+    use super::super::process_limits::Limit;
+    use super::super::ProbeError;
+    use super::os::{read_and_count_fd_dir, read_and_parse_file_nr};
+    use super::{ProcessFileDescriptors, SystemFileDescriptors};
+    use std::path::Path;
+
+    #[test]
+    fn test_read_self() {
+        let fds = super::read_self().unwrap();
+        // At least stdin, stdout and stderr
+        assert!(fds.open >= 3);
+        assert!(fds.usage_percentage().is_some());
+    }
+
+    #[test]
+    fn test_read_of() {
+        let pid = unsafe { libc::getpid() };
+        assert!(super::read_of(pid).is_ok());
+    }
+
+    #[test]
+    fn test_read_of_invalid_pid() {
+        assert!(super::read_of(0).is_err());
+    }
+
+    #[test]
+    fn test_read_system() {
+        let fds = super::read_system().unwrap();
+        assert!(fds.allocated > 0);
+        assert!(fds.max > 0);
+    }
+
+    #[test]
+    fn test_read_and_count_fd_dir() {
+        let path = Path::new("fixtures/linux/file_descriptors/proc_self_fd");
+        assert_eq!(4, read_and_count_fd_dir(path).unwrap());
+    }
+
+    #[test]
+    fn test_read_and_count_fd_dir_wrong_path() {
+        match read_and_count_fd_dir(Path::new("/nonsense")) {
+            Err(ProbeError::IO(_, _)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_file_nr() {
+        let fds = read_and_parse_file_nr(
+            Path::new("fixtures/linux/file_descriptors/file-nr"),
+            Path::new("fixtures/linux/file_descriptors/file-max"),
+        )
+        .unwrap();
+
+        let expected = SystemFileDescriptors {
+            allocated: 9856,
+            unused: 0,
+            max: 197120,
+        };
+        assert_eq!(expected, fds);
+        assert_eq!(5.0, fds.usage_percentage());
+    }
+
+    #[test]
+    fn test_read_and_parse_file_nr_incomplete() {
+        match read_and_parse_file_nr(
+            Path::new("fixtures/linux/file_descriptors/file-nr_incomplete"),
+            Path::new("fixtures/linux/file_descriptors/file-max"),
+        ) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_file_nr_garbage() {
+        match read_and_parse_file_nr(
+            Path::new("fixtures/linux/file_descriptors/file-nr_garbage"),
+            Path::new("fixtures/linux/file_descriptors/file-max"),
+        ) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_usage_percentage() {
+        let fds = ProcessFileDescriptors {
+            open: 256,
+            limit: Limit {
+                soft: Some(1024),
+                hard: Some(4096),
+            },
+        };
+        assert_eq!(Some(25.0), fds.usage_percentage());
+    }
+
+    #[test]
+    fn test_usage_percentage_unlimited() {
+        let fds = ProcessFileDescriptors {
+            open: 256,
+            limit: Limit {
+                soft: None,
+                hard: None,
+            },
+        };
+        assert_eq!(None, fds.usage_percentage());
+    }
+}
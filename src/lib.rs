@@ -1,13 +1,25 @@
 extern crate libc;
 
+pub mod cgroup;
+pub mod container;
 pub mod cpu;
 pub mod disk_stats;
 pub mod disk_usage;
 mod error;
+pub mod file_descriptors;
 pub mod load;
 pub mod memory;
 pub mod network;
+pub mod network_interfaces;
+pub mod network_protocols;
+pub mod pressure;
+pub mod process_io;
+pub mod process_limits;
 pub mod process_memory;
+pub mod process_status;
+pub mod processes;
+pub mod sockets;
+pub mod threads;
 
 use std::fs;
 use std::io;
@@ -81,6 +93,13 @@ fn parse_u64(segment: &str) -> Result<u64> {
         .map_err(|_| ProbeError::UnexpectedContent(format!("Could not parse '{}' as u64", segment)))
 }
 
+#[inline]
+fn parse_i64(segment: &str) -> Result<i64> {
+    segment
+        .parse()
+        .map_err(|_| ProbeError::UnexpectedContent(format!("Could not parse '{}' as i64", segment)))
+}
+
 #[inline]
 fn dir_exists(path: &Path) -> bool {
     path.exists() && path.is_dir()
@@ -104,6 +123,15 @@ fn precise_time_ns() -> u64 {
         .as_nanos() as u64;
 }
 
+/// Convert a value in clock ticks, as reported in `/proc/<pid>/stat`, to nanoseconds. The
+/// multiplication is done in u128, cumulative CPU times of busy processes overflow u64 otherwise.
+#[cfg(target_os = "linux")]
+#[inline]
+fn ticks_to_ns(ticks: u64) -> u64 {
+    let ticks_per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) } as u128;
+    (ticks as u128 * 1_000_000_000 / ticks_per_second) as u64
+}
+
 fn bytes_to_kilo_bytes(bytes: u64) -> u64 {
     bytes.checked_div(1024).unwrap_or(0)
 }
@@ -147,4 +175,22 @@ mod tests {
         assert_eq!(100, super::parse_u64("100").unwrap());
         assert!(super::parse_u64("something").is_err());
     }
+
+    #[test]
+    fn test_parse_i64() {
+        assert_eq!(-100, super::parse_i64("-100").unwrap());
+        assert!(super::parse_i64("something").is_err());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_ticks_to_ns() {
+        let ticks_per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) } as u64;
+        assert_eq!(1_000_000_000, super::ticks_to_ns(ticks_per_second));
+        // About 6 CPU-years at 100 ticks per second, this overflows u64 when multiplied first
+        assert_eq!(
+            20_000_000_000 / ticks_per_second * 1_000_000_000,
+            super::ticks_to_ns(20_000_000_000)
+        );
+    }
 }
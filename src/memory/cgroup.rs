@@ -1,38 +1,397 @@
-use std::path::Path;
-
 use super::Memory;
+use crate::cgroup::{self, Cgroups};
 use crate::{dir_exists, ProbeError, Result};
+use std::path::Path;
+
+/// Detailed memory statistics of a cgroup v2 cgroup from `memory.current` and `memory.stat`.
+/// Sizes are in Kb.
+#[derive(Debug, PartialEq)]
+pub struct CgroupMemoryStat {
+    pub current: u64,
+    pub anon: u64,
+    /// Page cache, including tmpfs and shared memory.
+    pub file: u64,
+    pub kernel_stack: u64,
+    pub slab_reclaimable: u64,
+    pub slab_unreclaimable: u64,
+    /// Memory used in network transmission buffers.
+    pub sock: u64,
+    pub shmem: u64,
+    pub file_dirty: u64,
+    pub file_writeback: u64,
+    pub active_file: u64,
+    pub inactive_file: u64,
+    /// Number of page faults.
+    pub pgfault: u64,
+    /// Number of major page faults.
+    pub pgmajfault: u64,
+}
+
+impl CgroupMemoryStat {
+    /// Memory that can't be reclaimed easily, calculated the way the kubelet does for its
+    /// eviction decisions.
+    pub fn working_set(&self) -> u64 {
+        self.current.saturating_sub(self.inactive_file)
+    }
+}
+
+/// A memory boundary of a cgroup v2 cgroup.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CgroupMemoryBoundary {
+    /// Set to `max`. For limits this means unlimited, for protections this means all memory of
+    /// the cgroup is protected.
+    Max,
+    /// Boundary in Kb.
+    Value(u64),
+    /// Not supported by the running kernel.
+    Unsupported,
+}
+
+/// Memory boundaries configured for a cgroup v2 cgroup.
+#[derive(Debug, PartialEq)]
+pub struct CgroupMemoryLimits {
+    /// Hard limit, processes get OOM killed when it can't be enforced.
+    pub max: CgroupMemoryBoundary,
+    /// Throttle limit, usage above it is reclaimed under heavy pressure.
+    pub high: CgroupMemoryBoundary,
+    /// Best-effort protection from reclaim.
+    pub low: CgroupMemoryBoundary,
+    /// Hard protection from reclaim.
+    pub min: CgroupMemoryBoundary,
+    /// Highest recorded usage in Kb, since Linux 5.19.
+    pub peak: Option<u64>,
+    pub swap_max: CgroupMemoryBoundary,
+    pub swap_high: CgroupMemoryBoundary,
+}
+
+/// Memory events of a cgroup, counted since the cgroup was created.
+#[derive(Debug, PartialEq)]
+pub struct CgroupMemoryEvents {
+    /// Times the usage was reclaimed while below `memory.low`, only with cgroup v2.
+    pub low: Option<u64>,
+    /// Times the usage exceeded `memory.high` and was throttled, only with cgroup v2.
+    pub high: Option<u64>,
+    /// Times the usage was about to exceed the limit, `memory.failcnt` with cgroup v1.
+    pub max: u64,
+    /// Times the OOM killer was invoked, only with cgroup v2.
+    pub oom: Option<u64>,
+    /// Number of processes killed by the OOM killer, not reported by cgroup v1 before Linux 4.13.
+    pub oom_kill: Option<u64>,
+    /// Times the whole cgroup was killed by the OOM killer, only with cgroup v2 on Linux 5.17+.
+    pub oom_group_kill: Option<u64>,
+    /// Highest recorded usage in Kb, only with cgroup v1.
+    pub max_usage: Option<u64>,
+    /// Whether the cgroup is currently out of memory, only with cgroup v1.
+    pub under_oom: Option<bool>,
+}
+
+impl CgroupMemoryEvents {
+    /// Calculate the number of events that happened between this and the next reading.
+    pub fn calculate_delta(
+        &self,
+        next_events: &CgroupMemoryEvents,
+    ) -> Result<CgroupMemoryEventsDelta> {
+        Ok(CgroupMemoryEventsDelta {
+            low: delta_of("low", self.low, next_events.low)?,
+            high: delta_of("high", self.high, next_events.high)?,
+            max: delta("max", self.max, next_events.max)?,
+            oom: delta_of("oom", self.oom, next_events.oom)?,
+            oom_kill: delta_of("oom_kill", self.oom_kill, next_events.oom_kill)?,
+            oom_group_kill: delta_of(
+                "oom_group_kill",
+                self.oom_group_kill,
+                next_events.oom_group_kill,
+            )?,
+        })
+    }
+}
+
+/// Number of memory events that happened between two readings.
+#[derive(Debug, PartialEq)]
+pub struct CgroupMemoryEventsDelta {
+    pub low: Option<u64>,
+    pub high: Option<u64>,
+    pub max: u64,
+    pub oom: Option<u64>,
+    pub oom_kill: Option<u64>,
+    pub oom_group_kill: Option<u64>,
+}
+
+fn delta(field_name: &str, value: u64, next_value: u64) -> Result<u64> {
+    next_value.checked_sub(value).ok_or_else(|| {
+        ProbeError::UnexpectedContent(format!(
+            "Value of {} is lower than the previous value",
+            field_name
+        ))
+    })
+}
+
+fn delta_of(field_name: &str, value: Option<u64>, next_value: Option<u64>) -> Result<Option<u64>> {
+    match (value, next_value) {
+        (Some(value), Some(next_value)) => delta(field_name, value, next_value).map(Some),
+        _ => Ok(None),
+    }
+}
 
 /// Read the current memory status of the container.
 #[cfg(target_os = "linux")]
 pub fn read() -> Result<Memory> {
+    read_from_cgroups(&cgroup::read()?)
+}
+
+/// Read the current memory status of the cgroup of a process with given pid.
+#[cfg(target_os = "linux")]
+pub fn read_of(pid: libc::pid_t) -> Result<Memory> {
+    read_from_cgroups(&cgroup::read_of(pid)?)
+}
+
+/// Read the detailed memory statistics of the container, only available with cgroup v2.
+#[cfg(target_os = "linux")]
+pub fn read_stat() -> Result<CgroupMemoryStat> {
+    read_stat_from_cgroups(&cgroup::read()?)
+}
+
+/// Read the detailed memory statistics of the cgroup of a process with given pid, only available
+/// with cgroup v2.
+#[cfg(target_os = "linux")]
+pub fn read_stat_of(pid: libc::pid_t) -> Result<CgroupMemoryStat> {
+    read_stat_from_cgroups(&cgroup::read_of(pid)?)
+}
+
+/// Read the memory boundaries of the container, only available with cgroup v2.
+#[cfg(target_os = "linux")]
+pub fn read_limits() -> Result<CgroupMemoryLimits> {
+    read_limits_from_cgroups(&cgroup::read()?)
+}
+
+/// Read the memory boundaries of the cgroup of a process with given pid, only available with
+/// cgroup v2.
+#[cfg(target_os = "linux")]
+pub fn read_limits_of(pid: libc::pid_t) -> Result<CgroupMemoryLimits> {
+    read_limits_from_cgroups(&cgroup::read_of(pid)?)
+}
+
+/// Read the memory events of the container.
+#[cfg(target_os = "linux")]
+pub fn read_events() -> Result<CgroupMemoryEvents> {
+    read_events_from_cgroups(&cgroup::read()?)
+}
+
+/// Read the memory events of the cgroup of a process with given pid.
+#[cfg(target_os = "linux")]
+pub fn read_events_of(pid: libc::pid_t) -> Result<CgroupMemoryEvents> {
+    read_events_from_cgroups(&cgroup::read_of(pid)?)
+}
+
+#[cfg(target_os = "linux")]
+fn read_from_cgroups(cgroups: &Cgroups) -> Result<Memory> {
+    read_from_cgroup_dir(cgroups, Path::new(cgroup::SYS_FS_CGROUP_DIR))
+}
+
+#[cfg(target_os = "linux")]
+fn read_from_cgroup_dir(cgroups: &Cgroups, cgroup_dir: &Path) -> Result<Memory> {
     use super::cgroup_v1::read_and_parse_v1_sys_memory;
     use super::cgroup_v2::read_and_parse_v2_sys_memory;
 
-    let v2_sys_fs_dir = Path::new("/sys/fs/cgroup");
-    let v2_sys_fs_file = v2_sys_fs_dir.join("memory.current");
+    // On hybrid systems the unified hierarchy is present as well, but the memory controller is
+    // bound to the v1 hierarchy.
+    if let Some(v1_sys_fs_dir) = cgroups.controller_dir_in(cgroup_dir, "memory") {
+        if dir_exists(&v1_sys_fs_dir) {
+            return read_and_parse_v1_sys_memory(&v1_sys_fs_dir);
+        }
+    }
+
+    if let Some(v2_sys_fs_dir) = cgroups.unified_dir_in(cgroup_dir) {
+        if v2_sys_fs_dir.join("memory.current").exists() {
+            return read_and_parse_v2_sys_memory(&v2_sys_fs_dir);
+        }
+    }
+
+    Err(ProbeError::UnexpectedContent(
+        "No cgroup v1 or v2 memory stats found".to_owned(),
+    ))
+}
+
+#[cfg(target_os = "linux")]
+fn read_stat_from_cgroups(cgroups: &Cgroups) -> Result<CgroupMemoryStat> {
+    use super::cgroup_v2::read_and_parse_v2_sys_memory_stat;
+
+    match cgroups.unified_dir() {
+        Some(v2_sys_fs_dir) if v2_sys_fs_dir.join("memory.stat").exists() => {
+            read_and_parse_v2_sys_memory_stat(&v2_sys_fs_dir)
+        }
+        _ => Err(ProbeError::UnexpectedContent(
+            "No cgroup v2 memory stats found".to_owned(),
+        )),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_limits_from_cgroups(cgroups: &Cgroups) -> Result<CgroupMemoryLimits> {
+    use super::cgroup_v2::read_and_parse_v2_sys_memory_limits;
+
+    match cgroups.unified_dir() {
+        Some(v2_sys_fs_dir) if v2_sys_fs_dir.join("memory.max").exists() => {
+            read_and_parse_v2_sys_memory_limits(&v2_sys_fs_dir)
+        }
+        _ => Err(ProbeError::UnexpectedContent(
+            "No cgroup v2 memory limits found".to_owned(),
+        )),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_events_from_cgroups(cgroups: &Cgroups) -> Result<CgroupMemoryEvents> {
+    use super::cgroup_v1::read_and_parse_v1_sys_memory_events;
+    use super::cgroup_v2::read_and_parse_v2_sys_memory_events;
 
-    if v2_sys_fs_file.exists() {
-        return read_and_parse_v2_sys_memory(&v2_sys_fs_dir);
+    if let Some(v1_sys_fs_dir) = cgroups.controller_dir("memory") {
+        if dir_exists(&v1_sys_fs_dir) {
+            return read_and_parse_v1_sys_memory_events(&v1_sys_fs_dir);
+        }
     }
 
-    let v1_sys_fs_dir = Path::new("/sys/fs/cgroup/memory/");
-    if dir_exists(v1_sys_fs_dir) {
-        return read_and_parse_v1_sys_memory(&v1_sys_fs_dir);
+    if let Some(v2_sys_fs_dir) = cgroups.unified_dir() {
+        if v2_sys_fs_dir.join("memory.events").exists() {
+            return read_and_parse_v2_sys_memory_events(&v2_sys_fs_dir);
+        }
     }
 
-    let message = format!(
-        "Directory `{}` not found",
-        v1_sys_fs_dir.to_str().unwrap_or("unknown path")
-    );
-    Err(ProbeError::UnexpectedContent(message))
+    Err(ProbeError::UnexpectedContent(
+        "No cgroup v1 or v2 memory events found".to_owned(),
+    ))
 }
 
 #[cfg(test)]
 #[cfg(target_os = "linux")]
 mod tests {
+    use super::{CgroupMemoryEvents, CgroupMemoryEventsDelta};
+    use crate::cgroup::os::read_and_parse_proc_cgroup;
+    use crate::ProbeError;
+    use std::path::Path;
+
     #[test]
     fn test_read_from_container() {
         assert!(super::read().is_ok());
     }
+
+    #[test]
+    fn test_read_of() {
+        let pid = unsafe { libc::getpid() };
+        assert_eq!(
+            super::read_of(pid).unwrap().total,
+            super::read().unwrap().total
+        );
+    }
+
+    #[test]
+    fn test_read_of_invalid_pid() {
+        match super::read_of(0) {
+            Err(ProbeError::IO(_, _)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_from_cgroup_dir_hybrid() {
+        let cgroups =
+            read_and_parse_proc_cgroup(Path::new("fixtures/linux/proc/self/cgroup/hybrid"))
+                .unwrap();
+        let memory =
+            super::read_from_cgroup_dir(&cgroups, Path::new("fixtures/linux/sys/fs/cgroup_hybrid"))
+                .unwrap();
+
+        assert_eq!(Some(512000), memory.total);
+    }
+
+    #[test]
+    fn test_read_from_cgroup_dir_missing_cgroup_of_other_process() {
+        // The cgroup of the process is not mounted, its stats should not be read from the root
+        let cgroups =
+            read_and_parse_proc_cgroup(Path::new("fixtures/linux/proc/self/cgroup/docker"))
+                .unwrap();
+        match super::read_from_cgroup_dir(
+            &cgroups,
+            Path::new("fixtures/linux/sys/fs/cgroup_hybrid"),
+        ) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_events() {
+        assert!(super::read_events().is_ok());
+    }
+
+    #[test]
+    fn test_read_events_of_invalid_pid() {
+        match super::read_events_of(0) {
+            Err(ProbeError::IO(_, _)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_calculate_delta() {
+        let events1 = CgroupMemoryEvents {
+            low: Some(0),
+            high: Some(12),
+            max: 3,
+            oom: Some(1),
+            oom_kill: Some(1),
+            oom_group_kill: None,
+            max_usage: None,
+            under_oom: None,
+        };
+        let events2 = CgroupMemoryEvents {
+            low: Some(0),
+            high: Some(40),
+            max: 5,
+            oom: Some(2),
+            oom_kill: Some(3),
+            oom_group_kill: Some(0),
+            max_usage: None,
+            under_oom: None,
+        };
+
+        let expected = CgroupMemoryEventsDelta {
+            low: Some(0),
+            high: Some(28),
+            max: 2,
+            oom: Some(1),
+            oom_kill: Some(2),
+            oom_group_kill: None,
+        };
+        assert_eq!(expected, events1.calculate_delta(&events2).unwrap());
+    }
+
+    #[test]
+    fn test_calculate_delta_values_lower() {
+        let events1 = CgroupMemoryEvents {
+            low: None,
+            high: None,
+            max: 10,
+            oom: None,
+            oom_kill: Some(1),
+            oom_group_kill: None,
+            max_usage: Some(1024),
+            under_oom: Some(false),
+        };
+        let events2 = CgroupMemoryEvents {
+            low: None,
+            high: None,
+            max: 5,
+            oom: None,
+            oom_kill: Some(1),
+            oom_group_kill: None,
+            max_usage: Some(1024),
+            under_oom: Some(false),
+        };
+
+        match events1.calculate_delta(&events2) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
 }
@@ -1,6 +1,7 @@
 use std::io::BufRead;
 use std::path::Path;
 
+use super::cgroup::CgroupMemoryEvents;
 use super::Memory;
 use crate::{bytes_to_kilo_bytes, file_to_buf_reader, parse_u64, read_file_value_as_u64};
 use crate::{path_to_string, ProbeError, Result};
@@ -65,9 +66,54 @@ pub fn read_and_parse_v1_sys_memory(path: &Path) -> Result<Memory> {
     Ok(memory)
 }
 
+#[cfg(target_os = "linux")]
+pub fn read_and_parse_v1_sys_memory_events(path: &Path) -> Result<CgroupMemoryEvents> {
+    let mut events = CgroupMemoryEvents {
+        low: None,
+        high: None,
+        max: read_file_value_as_u64(&path.join("memory.failcnt"))?,
+        oom: None,
+        oom_kill: None,
+        oom_group_kill: None,
+        max_usage: Some(bytes_to_kilo_bytes(read_file_value_as_u64(
+            &path.join("memory.max_usage_in_bytes"),
+        )?)),
+        under_oom: None,
+    };
+
+    let oom_control_path = path.join("memory.oom_control");
+    let reader = file_to_buf_reader(&oom_control_path)?;
+    for line_result in reader.lines() {
+        let line = line_result.map_err(|e| ProbeError::IO(e, path_to_string(&oom_control_path)))?;
+        let segments: Vec<&str> = line.split_whitespace().collect();
+        if segments.len() != 2 {
+            return Err(ProbeError::UnexpectedContent(format!(
+                "Unexpected line '{}'",
+                line
+            )));
+        }
+
+        match segments[0] {
+            "under_oom" => events.under_oom = Some(parse_u64(segments[1])? == 1),
+            // Only reported since Linux 4.13
+            "oom_kill" => events.oom_kill = Some(parse_u64(segments[1])?),
+            _ => (),
+        }
+    }
+
+    if events.under_oom.is_none() {
+        return Err(ProbeError::UnexpectedContent(
+            "Did not encounter all expected fields".to_owned(),
+        ));
+    }
+
+    Ok(events)
+}
+
 #[cfg(test)]
 #[cfg(target_os = "linux")]
 mod tests {
+    use super::super::cgroup::CgroupMemoryEvents;
     use super::super::Memory;
     use crate::ProbeError;
     use std::path::Path;
@@ -154,4 +200,59 @@ mod tests {
         assert_eq!(memory.swap_free, None);
         assert_eq!(memory.swap_used, None);
     }
+
+    #[test]
+    fn test_read_and_parse_v1_sys_memory_events() {
+        let path = Path::new("fixtures/linux/sys/fs/cgroup_v1/memory/");
+        let events = super::read_and_parse_v1_sys_memory_events(path).unwrap();
+
+        let expected = CgroupMemoryEvents {
+            low: None,
+            high: None,
+            max: 14,
+            oom: None,
+            oom_kill: Some(3),
+            oom_group_kill: None,
+            max_usage: Some(511872),
+            under_oom: Some(true),
+        };
+        assert_eq!(expected, events);
+    }
+
+    #[test]
+    fn test_read_and_parse_v1_sys_memory_events_without_oom_kill() {
+        let path = Path::new("fixtures/linux/sys/fs/cgroup_v1/memory_without_swap/");
+        let events = super::read_and_parse_v1_sys_memory_events(path).unwrap();
+
+        assert_eq!(0, events.max);
+        assert_eq!(None, events.oom_kill);
+        assert_eq!(Some(false), events.under_oom);
+    }
+
+    #[test]
+    fn test_read_and_parse_v1_sys_memory_events_missing_files() {
+        let path = Path::new("fixtures/linux/sys/fs/cgroup_v1/memory_missing_files/");
+        match super::read_and_parse_v1_sys_memory_events(path) {
+            Err(ProbeError::IO(_, _)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_v1_sys_memory_events_incomplete() {
+        let path = Path::new("fixtures/linux/sys/fs/cgroup_v1/memory_incomplete/");
+        match super::read_and_parse_v1_sys_memory_events(path) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_v1_sys_memory_events_garbage() {
+        let path = Path::new("fixtures/linux/sys/fs/cgroup_v1/memory_garbage/");
+        match super::read_and_parse_v1_sys_memory_events(path) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
 }
@@ -1,8 +1,14 @@
+use std::collections::HashMap;
 use std::io::BufRead;
 use std::path::Path;
 
+use super::cgroup::{
+    CgroupMemoryBoundary, CgroupMemoryEvents, CgroupMemoryLimits, CgroupMemoryStat,
+};
 use super::Memory;
-use crate::{bytes_to_kilo_bytes, file_to_buf_reader, parse_u64, read_file_value_as_u64};
+use crate::{
+    bytes_to_kilo_bytes, file_to_buf_reader, file_to_string, parse_u64, read_file_value_as_u64,
+};
 use crate::{path_to_string, ProbeError, Result};
 #[cfg(target_os = "linux")]
 pub fn read_and_parse_v2_sys_memory(path: &Path) -> Result<Memory> {
@@ -24,17 +30,9 @@ pub fn read_and_parse_v2_sys_memory(path: &Path) -> Result<Memory> {
 
     memory.used = bytes_to_kilo_bytes(read_file_value_as_u64(&path.join("memory.current"))?);
 
-    let reader = file_to_buf_reader(&path.join("memory.stat"))?;
-    for line_result in reader.lines() {
-        let line = line_result.map_err(|e| ProbeError::IO(e, path_to_string(path)))?;
-        let segments: Vec<&str> = line.split_whitespace().collect();
-        let value = parse_u64(&segments[1])?;
-
-        if segments[0] == "shmem" {
-            memory.shmem = Some(bytes_to_kilo_bytes(value));
-            break;
-        };
-    }
+    let stat = read_and_parse_v2_keyed_file(&path.join("memory.stat"))?;
+    memory.cached = stat.get("file").copied().map(bytes_to_kilo_bytes);
+    memory.shmem = stat.get("shmem").copied().map(bytes_to_kilo_bytes);
 
     memory.free = memory.total.map(|total| total - memory.used);
 
@@ -52,9 +50,126 @@ pub fn read_and_parse_v2_sys_memory(path: &Path) -> Result<Memory> {
     Ok(memory)
 }
 
+#[cfg(target_os = "linux")]
+pub fn read_and_parse_v2_sys_memory_stat(path: &Path) -> Result<CgroupMemoryStat> {
+    let current = bytes_to_kilo_bytes(read_file_value_as_u64(&path.join("memory.current"))?);
+    let stat = read_and_parse_v2_keyed_file(&path.join("memory.stat"))?;
+
+    let field = |name: &str| match stat.get(name) {
+        Some(value) => Ok(*value),
+        None => Err(ProbeError::UnexpectedContent(format!(
+            "Did not encounter expected field '{}'",
+            name
+        ))),
+    };
+    let size_field = |name: &str| field(name).map(bytes_to_kilo_bytes);
+
+    Ok(CgroupMemoryStat {
+        current,
+        anon: size_field("anon")?,
+        file: size_field("file")?,
+        kernel_stack: size_field("kernel_stack")?,
+        slab_reclaimable: size_field("slab_reclaimable")?,
+        slab_unreclaimable: size_field("slab_unreclaimable")?,
+        sock: size_field("sock")?,
+        shmem: size_field("shmem")?,
+        file_dirty: size_field("file_dirty")?,
+        file_writeback: size_field("file_writeback")?,
+        active_file: size_field("active_file")?,
+        inactive_file: size_field("inactive_file")?,
+        pgfault: field("pgfault")?,
+        pgmajfault: field("pgmajfault")?,
+    })
+}
+
+#[cfg(target_os = "linux")]
+pub fn read_and_parse_v2_sys_memory_events(path: &Path) -> Result<CgroupMemoryEvents> {
+    let events = read_and_parse_v2_keyed_file(&path.join("memory.events"))?;
+
+    let field = |name: &str| match events.get(name) {
+        Some(value) => Ok(*value),
+        None => Err(ProbeError::UnexpectedContent(format!(
+            "Did not encounter expected field '{}'",
+            name
+        ))),
+    };
+
+    Ok(CgroupMemoryEvents {
+        low: Some(field("low")?),
+        high: Some(field("high")?),
+        max: field("max")?,
+        // Only reported since kernel 4.13
+        oom: events.get("oom").copied(),
+        oom_kill: events.get("oom_kill").copied(),
+        oom_group_kill: events.get("oom_group_kill").copied(),
+        max_usage: None,
+        under_oom: None,
+    })
+}
+
+#[cfg(target_os = "linux")]
+pub fn read_and_parse_v2_sys_memory_limits(path: &Path) -> Result<CgroupMemoryLimits> {
+    let peak_path = path.join("memory.peak");
+    let peak = if peak_path.exists() {
+        Some(bytes_to_kilo_bytes(read_file_value_as_u64(&peak_path)?))
+    } else {
+        None
+    };
+
+    Ok(CgroupMemoryLimits {
+        max: read_v2_boundary(&path.join("memory.max"))?,
+        high: read_v2_boundary(&path.join("memory.high"))?,
+        low: read_v2_boundary(&path.join("memory.low"))?,
+        min: read_v2_boundary(&path.join("memory.min"))?,
+        peak,
+        swap_max: read_v2_boundary(&path.join("memory.swap.max"))?,
+        swap_high: read_v2_boundary(&path.join("memory.swap.high"))?,
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn read_v2_boundary(path: &Path) -> Result<CgroupMemoryBoundary> {
+    if !path.exists() {
+        return Ok(CgroupMemoryBoundary::Unsupported);
+    }
+
+    let value = file_to_string(path)?;
+    match value.trim() {
+        "max" => Ok(CgroupMemoryBoundary::Max),
+        value => {
+            parse_u64(value).map(|value| CgroupMemoryBoundary::Value(bytes_to_kilo_bytes(value)))
+        }
+    }
+}
+
+// columns: key value
+#[cfg(target_os = "linux")]
+fn read_and_parse_v2_keyed_file(path: &Path) -> Result<HashMap<String, u64>> {
+    let reader = file_to_buf_reader(path)?;
+
+    let mut stat = HashMap::new();
+    for line_result in reader.lines() {
+        let line = line_result.map_err(|e| ProbeError::IO(e, path_to_string(path)))?;
+        let segments: Vec<&str> = line.split_whitespace().collect();
+        if segments.len() != 2 {
+            return Err(ProbeError::UnexpectedContent(format!(
+                "Unexpected line '{}'",
+                line
+            )));
+        }
+
+        stat.insert(segments[0].to_owned(), parse_u64(segments[1])?);
+    }
+
+    Ok(stat)
+}
+
 #[cfg(test)]
 #[cfg(target_os = "linux")]
 mod tests {
+    use super::super::cgroup::{
+        CgroupMemoryBoundary, CgroupMemoryEvents, CgroupMemoryLimits, CgroupMemoryStat,
+    };
     use super::super::Memory;
     use crate::ProbeError;
     use std::path::Path;
@@ -69,7 +184,7 @@ mod tests {
             free: Some(444472),  // total - used
             used: 67528,
             buffers: None,
-            cached: None,
+            cached: Some(0),
             shmem: Some(0),
             swap_total: Some(2000000),  // reported swap total
             swap_free: Some(1_500_000), // swap total - swap used
@@ -129,7 +244,7 @@ mod tests {
             free: Some(444472),  // total - used
             used: 67528,
             buffers: None,
-            cached: None,
+            cached: Some(0),
             shmem: Some(0),
             swap_total: None, // Reads 0 swap
             swap_free: None,  // Reads 0 swap
@@ -141,4 +256,150 @@ mod tests {
         assert_eq!(memory.swap_free, None);
         assert_eq!(memory.swap_used, None);
     }
+
+    #[test]
+    fn test_read_and_parse_v2_sys_memory_stat() {
+        let path = Path::new("fixtures/linux/sys/fs/cgroup_v2/memory_stat/");
+        let stat = super::read_and_parse_v2_sys_memory_stat(path).unwrap();
+
+        let expected = CgroupMemoryStat {
+            current: 524288,
+            anon: 262144,
+            file: 204800,
+            kernel_stack: 1536,
+            slab_reclaimable: 12288,
+            slab_unreclaimable: 4096,
+            sock: 128,
+            shmem: 1024,
+            file_dirty: 256,
+            file_writeback: 64,
+            active_file: 81920,
+            inactive_file: 122880,
+            pgfault: 2389541,
+            pgmajfault: 712,
+        };
+        assert_eq!(expected, stat);
+        assert_eq!(401408, stat.working_set());
+    }
+
+    #[test]
+    fn test_read_and_parse_v2_sys_memory_stat_cached() {
+        let path = Path::new("fixtures/linux/sys/fs/cgroup_v2/memory_stat/");
+        let memory = super::read_and_parse_v2_sys_memory(path).unwrap();
+
+        assert_eq!(Some(204800), memory.cached);
+        assert_eq!(Some(1024), memory.shmem);
+    }
+
+    #[test]
+    fn test_read_and_parse_v2_sys_memory_stat_missing_fields() {
+        let path = Path::new("fixtures/linux/sys/fs/cgroup_v2/memory_stat_missing_fields/");
+        match super::read_and_parse_v2_sys_memory_stat(path) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_v2_sys_memory_stat_garbage() {
+        let path = Path::new("fixtures/linux/sys/fs/cgroup_v2/memory_garbage/");
+        match super::read_and_parse_v2_sys_memory_stat(path) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_v2_sys_memory_events() {
+        let path = Path::new("fixtures/linux/sys/fs/cgroup_v2/memory_stat/");
+        let events = super::read_and_parse_v2_sys_memory_events(path).unwrap();
+
+        let expected = CgroupMemoryEvents {
+            low: Some(0),
+            high: Some(1841),
+            max: 27,
+            oom: Some(2),
+            oom_kill: Some(1),
+            oom_group_kill: Some(0),
+            max_usage: None,
+            under_oom: None,
+        };
+        assert_eq!(expected, events);
+    }
+
+    #[test]
+    fn test_read_and_parse_v2_sys_memory_events_without_group_kill() {
+        let path = Path::new("fixtures/linux/sys/fs/cgroup_v2/memory/");
+        let events = super::read_and_parse_v2_sys_memory_events(path).unwrap();
+
+        assert_eq!(Some(0), events.oom_kill);
+        assert_eq!(None, events.oom_group_kill);
+    }
+
+    #[test]
+    fn test_read_and_parse_v2_sys_memory_events_without_oom() {
+        let path = Path::new("fixtures/linux/sys/fs/cgroup_v2/memory_without_swap/");
+        let events = super::read_and_parse_v2_sys_memory_events(path).unwrap();
+
+        assert_eq!(Some(12), events.high);
+        assert_eq!(3, events.max);
+        assert_eq!(None, events.oom);
+        assert_eq!(None, events.oom_kill);
+        assert_eq!(None, events.oom_group_kill);
+    }
+
+    #[test]
+    fn test_read_and_parse_v2_sys_memory_events_incomplete() {
+        let path = Path::new("fixtures/linux/sys/fs/cgroup_v2/memory_incomplete/");
+        match super::read_and_parse_v2_sys_memory_events(path) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_v2_sys_memory_events_garbage() {
+        let path = Path::new("fixtures/linux/sys/fs/cgroup_v2/memory_garbage/");
+        match super::read_and_parse_v2_sys_memory_events(path) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_v2_sys_memory_limits() {
+        let path = Path::new("fixtures/linux/sys/fs/cgroup_v2/memory_stat/");
+        let limits = super::read_and_parse_v2_sys_memory_limits(path).unwrap();
+
+        let expected = CgroupMemoryLimits {
+            max: CgroupMemoryBoundary::Value(1048576),
+            high: CgroupMemoryBoundary::Value(786432),
+            low: CgroupMemoryBoundary::Value(262144),
+            min: CgroupMemoryBoundary::Max,
+            peak: Some(601088),
+            swap_max: CgroupMemoryBoundary::Max,
+            swap_high: CgroupMemoryBoundary::Max,
+        };
+        assert_eq!(expected, limits);
+    }
+
+    #[test]
+    fn test_read_and_parse_v2_sys_memory_limits_missing_files() {
+        let path = Path::new("fixtures/linux/sys/fs/cgroup_v2/memory/");
+        let limits = super::read_and_parse_v2_sys_memory_limits(path).unwrap();
+
+        assert_eq!(CgroupMemoryBoundary::Value(512000), limits.max);
+        assert_eq!(CgroupMemoryBoundary::Unsupported, limits.high);
+        assert_eq!(None, limits.peak);
+        assert_eq!(CgroupMemoryBoundary::Value(2000000), limits.swap_max);
+    }
+
+    #[test]
+    fn test_read_and_parse_v2_sys_memory_limits_garbage() {
+        let path = Path::new("fixtures/linux/sys/fs/cgroup_v2/memory_garbage/");
+        match super::read_and_parse_v2_sys_memory_limits(path) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
 }
@@ -1,107 +1,215 @@
 use super::Memory;
 use crate::Result;
 
+/// All memory statistics of the system as reported in `/proc/meminfo`, in Kb. Fields that are not
+/// reported by the running kernel are `None`.
+#[derive(Debug, PartialEq)]
+pub struct MemInfo {
+    pub total: u64,
+    pub free: Option<u64>,
+    /// Estimate of the memory available for starting new applications without swapping.
+    pub available: Option<u64>,
+    pub buffers: Option<u64>,
+    pub cached: Option<u64>,
+    pub shmem: Option<u64>,
+    pub swap_total: Option<u64>,
+    pub swap_free: Option<u64>,
+    pub slab: Option<u64>,
+    pub slab_reclaimable: Option<u64>,
+    pub slab_unreclaimable: Option<u64>,
+    pub dirty: Option<u64>,
+    pub writeback: Option<u64>,
+    pub committed_as: Option<u64>,
+    pub commit_limit: Option<u64>,
+    pub anon_pages: Option<u64>,
+    pub mapped: Option<u64>,
+    pub page_tables: Option<u64>,
+    /// Number of huge pages, not in Kb.
+    pub huge_pages_total: Option<u64>,
+    pub huge_pages_free: Option<u64>,
+    pub huge_pages_reserved: Option<u64>,
+    pub huge_pages_surplus: Option<u64>,
+    pub huge_page_size: Option<u64>,
+}
+
 /// Read the current memory status of the system.
 #[cfg(target_os = "linux")]
 pub fn read() -> Result<Memory> {
     os::read()
 }
 
+/// Read the current memory status of the system, with `free` being the available memory and
+/// `used` calculated from it the way `free` does. Falls back to the result of `read` on kernels
+/// that don't report the available memory.
+#[cfg(target_os = "linux")]
+pub fn read_using_available() -> Result<Memory> {
+    os::read_using_available()
+}
+
+/// Read all memory statistics of the system.
+#[cfg(target_os = "linux")]
+pub fn read_meminfo() -> Result<MemInfo> {
+    os::read_meminfo()
+}
+
 #[cfg(target_os = "linux")]
 mod os {
     use std::io::BufRead;
     use std::path::Path;
 
     use super::super::Memory;
+    use super::MemInfo;
     use crate::{file_to_buf_reader, parse_u64};
     use crate::{path_to_string, ProbeError, Result};
 
-    const PROC_MEMORY_NUMBER_OF_FIELDS: usize = 7;
-
     #[inline]
     pub fn read() -> Result<Memory> {
         read_and_parse_proc_memory(&Path::new("/proc/meminfo"))
     }
 
+    #[inline]
+    pub fn read_using_available() -> Result<Memory> {
+        read_and_parse_proc_memory_using_available(Path::new("/proc/meminfo"))
+    }
+
+    #[inline]
+    pub fn read_meminfo() -> Result<MemInfo> {
+        read_and_parse_proc_meminfo(Path::new("/proc/meminfo"))
+    }
+
     #[inline]
     pub fn read_and_parse_proc_memory(path: &Path) -> Result<Memory> {
-        let mut memory = Memory {
-            total: None,
+        let meminfo = read_and_parse_proc_meminfo(path)?;
+        memory_from_meminfo(&meminfo)
+    }
+
+    pub fn read_and_parse_proc_memory_using_available(path: &Path) -> Result<Memory> {
+        let meminfo = read_and_parse_proc_meminfo(path)?;
+        let mut memory = memory_from_meminfo(&meminfo)?;
+
+        if let Some(available) = meminfo.available {
+            memory.free = Some(available);
+            memory.used = meminfo.total.saturating_sub(available);
+        }
+
+        Ok(memory)
+    }
+
+    fn memory_from_meminfo(meminfo: &MemInfo) -> Result<Memory> {
+        let (free, buffers, cached) = match (meminfo.free, meminfo.buffers, meminfo.cached) {
+            (Some(free), Some(buffers), Some(cached))
+                if meminfo.shmem.is_some()
+                    && meminfo.swap_total.is_some()
+                    && meminfo.swap_free.is_some() =>
+            {
+                (free, buffers, cached)
+            }
+            _ => {
+                return Err(ProbeError::UnexpectedContent(
+                    "Did not encounter all expected fields".to_owned(),
+                ))
+            }
+        };
+
+        // Total amount of free physical memory in Kb.
+        // Includes buffers and caches, these will be freed
+        // up by the OS when the memory is needed.
+        let free = free + buffers + cached;
+
+        Ok(Memory {
+            total: Some(meminfo.total),
+            free: Some(free),
+            used: meminfo.total - free,
+            buffers: Some(buffers),
+            cached: Some(cached),
+            shmem: meminfo.shmem,
+            swap_total: meminfo.swap_total,
+            swap_free: meminfo.swap_free,
+            swap_used: meminfo
+                .swap_total
+                .zip(meminfo.swap_free)
+                .map(|(total, free)| total - free),
+        })
+    }
+
+    pub fn read_and_parse_proc_meminfo(path: &Path) -> Result<MemInfo> {
+        let mut total = None;
+        let mut meminfo = MemInfo {
+            total: 0,
             free: None,
-            used: 0,
+            available: None,
             buffers: None,
             cached: None,
             shmem: None,
             swap_total: None,
             swap_free: None,
-            swap_used: None,
+            slab: None,
+            slab_reclaimable: None,
+            slab_unreclaimable: None,
+            dirty: None,
+            writeback: None,
+            committed_as: None,
+            commit_limit: None,
+            anon_pages: None,
+            mapped: None,
+            page_tables: None,
+            huge_pages_total: None,
+            huge_pages_free: None,
+            huge_pages_reserved: None,
+            huge_pages_surplus: None,
+            huge_page_size: None,
         };
-        let mut free = 0;
 
         let reader = file_to_buf_reader(path)?;
 
-        let mut fields_encountered = 0;
         for line_result in reader.lines() {
             let line = line_result.map_err(|e| ProbeError::IO(e, path_to_string(path)))?;
             let segments: Vec<&str> = line.split_whitespace().collect();
-            let value: u64 = parse_u64(segments[1])?;
-
-            // If this is a field we recognize set it's value and increment the
-            // number of fields we encountered.
-            fields_encountered += match segments[0] {
-                "MemTotal:" => {
-                    memory.total = Some(value);
-                    1
-                }
-                "MemFree:" => {
-                    free = value;
-                    1
-                }
-                "Buffers:" => {
-                    memory.buffers = Some(value);
-                    1
-                }
-                "Cached:" => {
-                    memory.cached = Some(value);
-                    1
-                }
-                "SwapTotal:" => {
-                    memory.swap_total = Some(value);
-                    1
-                }
-                "SwapFree:" => {
-                    memory.swap_free = Some(value);
-                    1
-                }
-                "Shmem:" => {
-                    memory.shmem = Some(value);
-                    1
-                }
-                _ => 0,
-            };
-
-            if fields_encountered == PROC_MEMORY_NUMBER_OF_FIELDS {
-                break;
+            if segments.len() < 2 {
+                return Err(ProbeError::UnexpectedContent(format!(
+                    "Unexpected line '{}'",
+                    line
+                )));
             }
+
+            let field = match segments[0] {
+                "MemTotal:" => &mut total,
+                "MemFree:" => &mut meminfo.free,
+                "MemAvailable:" => &mut meminfo.available,
+                "Buffers:" => &mut meminfo.buffers,
+                "Cached:" => &mut meminfo.cached,
+                "Shmem:" => &mut meminfo.shmem,
+                "SwapTotal:" => &mut meminfo.swap_total,
+                "SwapFree:" => &mut meminfo.swap_free,
+                "Slab:" => &mut meminfo.slab,
+                "SReclaimable:" => &mut meminfo.slab_reclaimable,
+                "SUnreclaim:" => &mut meminfo.slab_unreclaimable,
+                "Dirty:" => &mut meminfo.dirty,
+                "Writeback:" => &mut meminfo.writeback,
+                "Committed_AS:" => &mut meminfo.committed_as,
+                "CommitLimit:" => &mut meminfo.commit_limit,
+                "AnonPages:" => &mut meminfo.anon_pages,
+                "Mapped:" => &mut meminfo.mapped,
+                "PageTables:" => &mut meminfo.page_tables,
+                "HugePages_Total:" => &mut meminfo.huge_pages_total,
+                "HugePages_Free:" => &mut meminfo.huge_pages_free,
+                "HugePages_Rsvd:" => &mut meminfo.huge_pages_reserved,
+                "HugePages_Surp:" => &mut meminfo.huge_pages_surplus,
+                "Hugepagesize:" => &mut meminfo.huge_page_size,
+                _ => continue,
+            };
+            *field = Some(parse_u64(segments[1])?);
         }
 
-        if fields_encountered != PROC_MEMORY_NUMBER_OF_FIELDS || memory.total.is_none() {
-            return Err(ProbeError::UnexpectedContent(
+        match total {
+            Some(total) => {
+                meminfo.total = total;
+                Ok(meminfo)
+            }
+            None => Err(ProbeError::UnexpectedContent(
                 "Did not encounter all expected fields".to_owned(),
-            ));
+            )),
         }
-
-        // Total amount of free physical memory in Kb.
-        // Includes buffers and caches, these will be freed
-        // up by the OS when the memory is needed.
-        memory.free = Some(free + memory.buffers.unwrap_or(0) + memory.cached.unwrap_or(0));
-        memory.used = memory.total.unwrap() - memory.free.unwrap();
-        memory.swap_used = memory
-            .swap_total
-            .zip(memory.swap_free)
-            .map(|(total, free)| total - free);
-
-        Ok(memory)
     }
 }
 
@@ -109,6 +217,7 @@ mod os {
 #[cfg(target_os = "linux")]
 mod tests {
     use super::super::Memory;
+    use super::MemInfo;
     use crate::ProbeError;
     use std::path::Path;
 
@@ -141,6 +250,90 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_read_memory_using_available() {
+        assert!(super::read_using_available().is_ok());
+    }
+
+    #[test]
+    fn test_read_meminfo() {
+        assert!(super::read_meminfo().is_ok());
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_memory_using_available() {
+        let path = Path::new("fixtures/linux/memory/proc_meminfo_available");
+        let memory = super::os::read_and_parse_proc_memory_using_available(path).unwrap();
+
+        assert_eq!(Some(8041412), memory.total);
+        assert_eq!(Some(5231876), memory.free);
+        assert_eq!(2809536, memory.used);
+        assert_eq!(Some(152064), memory.swap_used);
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_memory_using_available_missing() {
+        let path = Path::new("fixtures/linux/memory/proc_meminfo");
+        let memory = super::os::read_and_parse_proc_memory_using_available(path).unwrap();
+
+        assert_eq!(Some(324248), memory.free);
+        assert_eq!(51824, memory.used);
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_meminfo() {
+        let path = Path::new("fixtures/linux/memory/proc_meminfo_available");
+        let meminfo = super::os::read_and_parse_proc_meminfo(path).unwrap();
+
+        let expected = MemInfo {
+            total: 8041412,
+            free: Some(612340),
+            available: Some(5231876),
+            buffers: Some(243812),
+            cached: Some(4512904),
+            shmem: Some(262104),
+            swap_total: Some(2097148),
+            swap_free: Some(1945084),
+            slab: Some(612784),
+            slab_reclaimable: Some(398112),
+            slab_unreclaimable: Some(214672),
+            dirty: Some(1372),
+            writeback: Some(16),
+            committed_as: Some(9482396),
+            commit_limit: Some(6117852),
+            anon_pages: Some(1972376),
+            mapped: Some(698024),
+            page_tables: Some(38412),
+            huge_pages_total: Some(64),
+            huge_pages_free: Some(60),
+            huge_pages_reserved: Some(2),
+            huge_pages_surplus: Some(0),
+            huge_page_size: Some(2048),
+        };
+        assert_eq!(expected, meminfo);
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_meminfo_missing_fields() {
+        let path = Path::new("fixtures/linux/memory/proc_meminfo_incomplete");
+        let meminfo = super::os::read_and_parse_proc_meminfo(path).unwrap();
+
+        assert_eq!(376072, meminfo.total);
+        assert_eq!(Some(125104), meminfo.free);
+        assert_eq!(None, meminfo.available);
+        assert_eq!(None, meminfo.slab);
+        assert_eq!(None, meminfo.huge_pages_total);
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_meminfo_garbage() {
+        let path = Path::new("fixtures/linux/memory/proc_meminfo_garbage");
+        match super::os::read_and_parse_proc_meminfo(path) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
     #[test]
     fn test_read_and_parse_memory_wrong_path() {
         let path = Path::new("/nonsense");
@@ -1,4 +1,4 @@
-use super::{calculate_time_difference, ProbeError, Result};
+use super::{calculate_time_difference, time_adjusted, Result};
 use std::collections::HashMap;
 
 pub type Interfaces = HashMap<String, NetworkTraffic>;
@@ -13,7 +13,9 @@ pub struct NetworkTrafficMeasurement {
 impl NetworkTrafficMeasurement {
     /// Calculate the network traffic per minute based on this measurement and a measurement in the
     /// future. It is advisable to make the next measurement roughly a minute from this one for the
-    /// most reliable result.
+    /// most reliable result. Interfaces that are not present in both measurements are listed as
+    /// added or removed, interfaces with counters that went down, for example because the
+    /// interface was re-created, are listed as reset. These are left out of the result.
     pub fn calculate_per_minute(
         &self,
         next_measurement: &NetworkTrafficMeasurement,
@@ -22,51 +24,237 @@ impl NetworkTrafficMeasurement {
             calculate_time_difference(self.precise_time_ns, next_measurement.precise_time_ns)?;
 
         let mut interfaces = Interfaces::new();
+        let mut removed = Vec::new();
+        let mut reset = Vec::new();
 
         for (name, traffic) in self.interfaces.iter() {
             let next_traffic = match next_measurement.interfaces.get(name) {
                 Some(interface) => interface,
                 None => {
-                    return Err(ProbeError::UnexpectedContent(format!(
-                        "{} is not present in the next measurement",
-                        name
-                    )))
+                    removed.push(name.to_owned());
+                    continue;
                 }
             };
+            if traffic.is_reset(next_traffic) {
+                reset.push(name.to_owned());
+                continue;
+            }
             interfaces.insert(
                 name.to_string(),
-                NetworkTraffic {
-                    received: super::time_adjusted(
-                        "received",
-                        next_traffic.received,
-                        traffic.received,
-                        time_difference,
-                    )?,
-                    transmitted: super::time_adjusted(
-                        "transmitted",
-                        next_traffic.transmitted,
-                        traffic.transmitted,
-                        time_difference,
-                    )?,
-                },
+                traffic.per_minute(next_traffic, time_difference)?,
             );
         }
 
-        Ok(NetworkTrafficPerMinute { interfaces })
+        let mut added: Vec<String> = next_measurement
+            .interfaces
+            .keys()
+            .filter(|name| !self.interfaces.contains_key(*name))
+            .cloned()
+            .collect();
+
+        added.sort();
+        removed.sort();
+        reset.sort();
+
+        Ok(NetworkTrafficPerMinute {
+            interfaces,
+            added,
+            removed,
+            reset,
+        })
     }
 }
 
-/// Network traffic in bytes.
+/// Network traffic in bytes and packets.
 #[derive(Debug, PartialEq)]
 pub struct NetworkTraffic {
+    /// Bytes received
     pub received: u64,
+    pub received_packets: u64,
+    pub received_errors: u64,
+    pub received_dropped: u64,
+    pub received_fifo_errors: u64,
+    pub received_frame_errors: u64,
+    pub received_compressed: u64,
+    pub received_multicast: u64,
+    /// Bytes transmitted
     pub transmitted: u64,
+    pub transmitted_packets: u64,
+    pub transmitted_errors: u64,
+    pub transmitted_dropped: u64,
+    pub transmitted_fifo_errors: u64,
+    pub transmitted_collisions: u64,
+    pub transmitted_carrier_errors: u64,
+    pub transmitted_compressed: u64,
+}
+
+impl NetworkTraffic {
+    /// Utilization of a link with given speed in Mbit/s, such as the speed of a
+    /// `NetworkInterface`. Only meaningful for network traffic per minute.
+    pub fn link_utilization(&self, speed: u64) -> Option<LinkUtilization> {
+        if speed == 0 {
+            return None;
+        }
+        // The capacity of the link in bytes per minute
+        let capacity = speed as f64 * 1_000_000.0 / 8.0 * 60.0;
+
+        Some(LinkUtilization {
+            received: (self.received as f64 / capacity * 100.0) as f32,
+            transmitted: (self.transmitted as f64 / capacity * 100.0) as f32,
+        })
+    }
+
+    fn is_reset(&self, next_traffic: &NetworkTraffic) -> bool {
+        self.counters()
+            .iter()
+            .zip(next_traffic.counters().iter())
+            .any(|(value, next_value)| next_value < value)
+    }
+
+    fn counters(&self) -> [u64; 16] {
+        [
+            self.received,
+            self.received_packets,
+            self.received_errors,
+            self.received_dropped,
+            self.received_fifo_errors,
+            self.received_frame_errors,
+            self.received_compressed,
+            self.received_multicast,
+            self.transmitted,
+            self.transmitted_packets,
+            self.transmitted_errors,
+            self.transmitted_dropped,
+            self.transmitted_fifo_errors,
+            self.transmitted_collisions,
+            self.transmitted_carrier_errors,
+            self.transmitted_compressed,
+        ]
+    }
+
+    fn per_minute(
+        &self,
+        next_traffic: &NetworkTraffic,
+        time_difference: u64,
+    ) -> Result<NetworkTraffic> {
+        Ok(NetworkTraffic {
+            received: time_adjusted(
+                "received",
+                next_traffic.received,
+                self.received,
+                time_difference,
+            )?,
+            received_packets: time_adjusted(
+                "received_packets",
+                next_traffic.received_packets,
+                self.received_packets,
+                time_difference,
+            )?,
+            received_errors: time_adjusted(
+                "received_errors",
+                next_traffic.received_errors,
+                self.received_errors,
+                time_difference,
+            )?,
+            received_dropped: time_adjusted(
+                "received_dropped",
+                next_traffic.received_dropped,
+                self.received_dropped,
+                time_difference,
+            )?,
+            received_fifo_errors: time_adjusted(
+                "received_fifo_errors",
+                next_traffic.received_fifo_errors,
+                self.received_fifo_errors,
+                time_difference,
+            )?,
+            received_frame_errors: time_adjusted(
+                "received_frame_errors",
+                next_traffic.received_frame_errors,
+                self.received_frame_errors,
+                time_difference,
+            )?,
+            received_compressed: time_adjusted(
+                "received_compressed",
+                next_traffic.received_compressed,
+                self.received_compressed,
+                time_difference,
+            )?,
+            received_multicast: time_adjusted(
+                "received_multicast",
+                next_traffic.received_multicast,
+                self.received_multicast,
+                time_difference,
+            )?,
+            transmitted: time_adjusted(
+                "transmitted",
+                next_traffic.transmitted,
+                self.transmitted,
+                time_difference,
+            )?,
+            transmitted_packets: time_adjusted(
+                "transmitted_packets",
+                next_traffic.transmitted_packets,
+                self.transmitted_packets,
+                time_difference,
+            )?,
+            transmitted_errors: time_adjusted(
+                "transmitted_errors",
+                next_traffic.transmitted_errors,
+                self.transmitted_errors,
+                time_difference,
+            )?,
+            transmitted_dropped: time_adjusted(
+                "transmitted_dropped",
+                next_traffic.transmitted_dropped,
+                self.transmitted_dropped,
+                time_difference,
+            )?,
+            transmitted_fifo_errors: time_adjusted(
+                "transmitted_fifo_errors",
+                next_traffic.transmitted_fifo_errors,
+                self.transmitted_fifo_errors,
+                time_difference,
+            )?,
+            transmitted_collisions: time_adjusted(
+                "transmitted_collisions",
+                next_traffic.transmitted_collisions,
+                self.transmitted_collisions,
+                time_difference,
+            )?,
+            transmitted_carrier_errors: time_adjusted(
+                "transmitted_carrier_errors",
+                next_traffic.transmitted_carrier_errors,
+                self.transmitted_carrier_errors,
+                time_difference,
+            )?,
+            transmitted_compressed: time_adjusted(
+                "transmitted_compressed",
+                next_traffic.transmitted_compressed,
+                self.transmitted_compressed,
+                time_difference,
+            )?,
+        })
+    }
+}
+
+/// Throughput of a link in percentages of its speed
+#[derive(Debug, PartialEq)]
+pub struct LinkUtilization {
+    pub received: f32,
+    pub transmitted: f32,
 }
 
 /// Network traffic for a certain minute, calculated based on two measurements.
 #[derive(Debug, PartialEq)]
 pub struct NetworkTrafficPerMinute {
     pub interfaces: Interfaces,
+    /// Interfaces only present in the next measurement
+    pub added: Vec<String>,
+    /// Interfaces only present in the first measurement
+    pub removed: Vec<String>,
+    /// Interfaces of which the counters were reset between the measurements
+    pub reset: Vec<String>,
 }
 
 #[cfg(target_os = "linux")]
@@ -103,10 +291,10 @@ mod os {
             let segments: Vec<&str> = line.split_whitespace().collect();
             let name = segments[0].trim_matches(':').to_owned();
 
-            if segments.len() < positions.transmit_bytes {
+            if segments.len() < positions.columns {
                 return Err(ProbeError::UnexpectedContent(format!(
                     "Expected at least {} items, had {} for '{}'",
-                    positions.transmit_bytes,
+                    positions.columns,
                     segments.len(),
                     name
                 )));
@@ -114,7 +302,21 @@ mod os {
 
             let traffic = NetworkTraffic {
                 received: parse_u64(segments[positions.receive_bytes])?,
+                received_packets: parse_u64(segments[positions.receive_packets])?,
+                received_errors: parse_u64(segments[positions.receive_errs])?,
+                received_dropped: parse_u64(segments[positions.receive_drop])?,
+                received_fifo_errors: parse_u64(segments[positions.receive_fifo])?,
+                received_frame_errors: parse_u64(segments[positions.receive_frame])?,
+                received_compressed: parse_u64(segments[positions.receive_compressed])?,
+                received_multicast: parse_u64(segments[positions.receive_multicast])?,
                 transmitted: parse_u64(segments[positions.transmit_bytes])?,
+                transmitted_packets: parse_u64(segments[positions.transmit_packets])?,
+                transmitted_errors: parse_u64(segments[positions.transmit_errs])?,
+                transmitted_dropped: parse_u64(segments[positions.transmit_drop])?,
+                transmitted_fifo_errors: parse_u64(segments[positions.transmit_fifo])?,
+                transmitted_collisions: parse_u64(segments[positions.transmit_colls])?,
+                transmitted_carrier_errors: parse_u64(segments[positions.transmit_carrier])?,
+                transmitted_compressed: parse_u64(segments[positions.transmit_compressed])?,
             };
 
             interfaces.insert(name, traffic);
@@ -128,11 +330,27 @@ mod os {
 
     #[derive(Debug, PartialEq)]
     pub struct Positions {
+        /// Number of columns including the interface name
+        pub columns: usize,
         pub receive_bytes: usize,
+        pub receive_packets: usize,
+        pub receive_errs: usize,
+        pub receive_drop: usize,
+        pub receive_fifo: usize,
+        pub receive_frame: usize,
+        pub receive_compressed: usize,
+        pub receive_multicast: usize,
         pub transmit_bytes: usize,
+        pub transmit_packets: usize,
+        pub transmit_errs: usize,
+        pub transmit_drop: usize,
+        pub transmit_fifo: usize,
+        pub transmit_colls: usize,
+        pub transmit_carrier: usize,
+        pub transmit_compressed: usize,
     }
 
-    /// Get the positions of the `bytes` field for both the receive and transmit segment
+    /// Get the positions of all fields for both the receive and transmit segment by their name
     #[inline]
     pub fn get_positions(header_line: &str) -> Result<Positions> {
         let groups: Vec<&str> = header_line.split('|').collect();
@@ -144,25 +362,44 @@ mod os {
         let receive_group: Vec<&str> = groups[1].split_whitespace().collect();
         let transmit_group: Vec<&str> = groups[2].split_whitespace().collect();
 
-        let receive_pos = receive_group
-            .iter()
-            .position(|&e| e == "bytes")
-            .ok_or_else(|| {
-                ProbeError::UnexpectedContent("bytes field not found for receive".to_string())
-            })?;
-        let transmit_pos = transmit_group
-            .iter()
-            .position(|&e| e == "bytes")
-            .ok_or_else(|| {
-                ProbeError::UnexpectedContent("bytes field not found for transmit".to_string())
-            })?;
-
         // We start with 1 here because the first (name) segment always has one column.
+        let receive = |field: &str| position(&receive_group, 1, field, "receive");
+        let transmit =
+            |field: &str| position(&transmit_group, 1 + receive_group.len(), field, "transmit");
+
         Ok(Positions {
-            receive_bytes: 1 + receive_pos,
-            transmit_bytes: 1 + receive_group.len() + transmit_pos,
+            columns: 1 + receive_group.len() + transmit_group.len(),
+            receive_bytes: receive("bytes")?,
+            receive_packets: receive("packets")?,
+            receive_errs: receive("errs")?,
+            receive_drop: receive("drop")?,
+            receive_fifo: receive("fifo")?,
+            receive_frame: receive("frame")?,
+            receive_compressed: receive("compressed")?,
+            receive_multicast: receive("multicast")?,
+            transmit_bytes: transmit("bytes")?,
+            transmit_packets: transmit("packets")?,
+            transmit_errs: transmit("errs")?,
+            transmit_drop: transmit("drop")?,
+            transmit_fifo: transmit("fifo")?,
+            transmit_colls: transmit("colls")?,
+            transmit_carrier: transmit("carrier")?,
+            transmit_compressed: transmit("compressed")?,
         })
     }
+
+    fn position(group: &[&str], offset: usize, field: &str, direction: &str) -> Result<usize> {
+        group
+            .iter()
+            .position(|&e| e == field)
+            .map(|position| offset + position)
+            .ok_or_else(|| {
+                ProbeError::UnexpectedContent(format!(
+                    "{} field not found for {}",
+                    field, direction
+                ))
+            })
+    }
 }
 
 #[cfg(test)]
@@ -199,6 +436,39 @@ mod tests {
         let eth1 = interfaces.get("eth1").unwrap();
         assert_eq!(354972, eth1.received);
         assert_eq!(82219, eth1.transmitted);
+
+        let expected = NetworkTraffic {
+            received: 254972,
+            received_packets: 1129,
+            received_errors: 3,
+            received_dropped: 17,
+            received_fifo_errors: 1,
+            received_frame_errors: 2,
+            received_compressed: 0,
+            received_multicast: 41,
+            transmitted: 72219,
+            transmitted_packets: 711,
+            transmitted_errors: 5,
+            transmitted_dropped: 9,
+            transmitted_fifo_errors: 0,
+            transmitted_collisions: 4,
+            transmitted_carrier_errors: 6,
+            transmitted_compressed: 0,
+        };
+        assert_eq!(&expected, eth0);
+    }
+
+    #[test]
+    fn test_read_and_parse_network_reordered() {
+        let path = Path::new("fixtures/linux/network/proc_net_dev_reordered");
+        let measurement = super::os::read_and_parse_network(path).unwrap();
+
+        let eth0 = measurement.interfaces.get("eth0").unwrap();
+        assert_eq!(254972, eth0.received);
+        assert_eq!(1129, eth0.received_packets);
+        assert_eq!(72219, eth0.transmitted);
+        assert_eq!(711, eth0.transmitted_packets);
+        assert_eq!(6, eth0.transmitted_carrier_errors);
     }
 
     #[test]
@@ -234,13 +504,39 @@ mod tests {
 
         assert_eq!(
             super::os::Positions {
+                columns: 17,
                 receive_bytes: 1,
-                transmit_bytes: 9
+                receive_packets: 2,
+                receive_errs: 3,
+                receive_drop: 4,
+                receive_fifo: 5,
+                receive_frame: 6,
+                receive_compressed: 7,
+                receive_multicast: 8,
+                transmit_bytes: 9,
+                transmit_packets: 10,
+                transmit_errs: 11,
+                transmit_drop: 12,
+                transmit_fifo: 13,
+                transmit_colls: 14,
+                transmit_carrier: 15,
+                transmit_compressed: 16,
             },
             super::os::get_positions(line).unwrap()
         )
     }
 
+    #[test]
+    fn test_get_positions_reordered() {
+        let line = "face |packets bytes errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls compressed carrier";
+
+        let positions = super::os::get_positions(line).unwrap();
+        assert_eq!(2, positions.receive_bytes);
+        assert_eq!(1, positions.receive_packets);
+        assert_eq!(16, positions.transmit_carrier);
+        assert_eq!(15, positions.transmit_compressed);
+    }
+
     #[test]
     fn test_get_positions_fields_missing() {
         let line = "face";
@@ -264,40 +560,16 @@ mod tests {
     #[test]
     fn test_calculate_per_minute_full_minute() {
         let mut interfaces1 = Interfaces::new();
-        interfaces1.insert(
-            "eth0".to_string(),
-            NetworkTraffic {
-                received: 1000,
-                transmitted: 1000,
-            },
-        );
-        interfaces1.insert(
-            "eth1".to_string(),
-            NetworkTraffic {
-                received: 2000,
-                transmitted: 3000,
-            },
-        );
+        interfaces1.insert("eth0".to_string(), helpers::network_traffic(1000, 1000));
+        interfaces1.insert("eth1".to_string(), helpers::network_traffic(2000, 3000));
         let measurement1 = NetworkTrafficMeasurement {
             precise_time_ns: 60_000_000_000,
             interfaces: interfaces1,
         };
 
         let mut interfaces2 = Interfaces::new();
-        interfaces2.insert(
-            "eth0".to_string(),
-            NetworkTraffic {
-                received: 2000,
-                transmitted: 2600,
-            },
-        );
-        interfaces2.insert(
-            "eth1".to_string(),
-            NetworkTraffic {
-                received: 3000,
-                transmitted: 4600,
-            },
-        );
+        interfaces2.insert("eth0".to_string(), helpers::network_traffic(2000, 2600));
+        interfaces2.insert("eth1".to_string(), helpers::network_traffic(3000, 4600));
         let measurement2 = NetworkTrafficMeasurement {
             precise_time_ns: 120_000_000_000,
             interfaces: interfaces2,
@@ -318,40 +590,16 @@ mod tests {
     #[test]
     fn test_calculate_per_minute_partial_minute() {
         let mut interfaces1 = Interfaces::new();
-        interfaces1.insert(
-            "eth0".to_string(),
-            NetworkTraffic {
-                received: 1000,
-                transmitted: 1000,
-            },
-        );
-        interfaces1.insert(
-            "eth1".to_string(),
-            NetworkTraffic {
-                received: 2000,
-                transmitted: 3000,
-            },
-        );
+        interfaces1.insert("eth0".to_string(), helpers::network_traffic(1000, 1000));
+        interfaces1.insert("eth1".to_string(), helpers::network_traffic(2000, 3000));
         let measurement1 = NetworkTrafficMeasurement {
             precise_time_ns: 60_000_000_000,
             interfaces: interfaces1,
         };
 
         let mut interfaces2 = Interfaces::new();
-        interfaces2.insert(
-            "eth0".to_string(),
-            NetworkTraffic {
-                received: 2000,
-                transmitted: 2600,
-            },
-        );
-        interfaces2.insert(
-            "eth1".to_string(),
-            NetworkTraffic {
-                received: 3000,
-                transmitted: 4600,
-            },
-        );
+        interfaces2.insert("eth0".to_string(), helpers::network_traffic(2000, 2600));
+        interfaces2.insert("eth1".to_string(), helpers::network_traffic(3000, 4600));
         let measurement2 = NetworkTrafficMeasurement {
             precise_time_ns: 90_000_000_000,
             interfaces: interfaces2,
@@ -367,6 +615,8 @@ mod tests {
         let eth1 = per_minute.interfaces.get("eth0").unwrap();
         assert_eq!(2000, eth1.received);
         assert_eq!(3200, eth1.transmitted);
+        assert_eq!(2000, eth1.received_dropped);
+        assert_eq!(3200, eth1.transmitted_errors);
     }
 
     #[test]
@@ -388,70 +638,118 @@ mod tests {
     }
 
     #[test]
-    fn test_calculate_per_minute_values_lower() {
+    fn test_calculate_per_minute_values_lower_is_reset() {
         let mut interfaces1 = Interfaces::new();
-        interfaces1.insert(
-            "eth0".to_string(),
-            NetworkTraffic {
-                received: 2000,
-                transmitted: 3000,
-            },
-        );
+        interfaces1.insert("eth0".to_string(), helpers::network_traffic(2000, 3000));
         let measurement1 = NetworkTrafficMeasurement {
             precise_time_ns: 60_000_000_000,
             interfaces: interfaces1,
         };
 
         let mut interfaces2 = Interfaces::new();
-        interfaces2.insert(
-            "eth0".to_string(),
-            NetworkTraffic {
-                received: 2000,
-                transmitted: 2600,
-            },
-        );
+        interfaces2.insert("eth0".to_string(), helpers::network_traffic(2000, 2600));
         let measurement2 = NetworkTrafficMeasurement {
             precise_time_ns: 120_000_000_000,
             interfaces: interfaces2,
         };
 
-        match measurement1.calculate_per_minute(&measurement2) {
-            Err(ProbeError::UnexpectedContent(_)) => (),
-            r => panic!("Unexpected result: {:?}", r),
-        }
+        let per_minute = measurement1.calculate_per_minute(&measurement2).unwrap();
+        assert!(per_minute.interfaces.is_empty());
+        assert_eq!(vec!["eth0".to_owned()], per_minute.reset);
     }
 
     #[test]
     fn test_calculate_per_minute_different_interfaces() {
         let mut interfaces1 = Interfaces::new();
-        interfaces1.insert(
-            "eth1".to_string(),
-            NetworkTraffic {
-                received: 2000,
-                transmitted: 3000,
-            },
-        );
+        interfaces1.insert("eth1".to_string(), helpers::network_traffic(2000, 3000));
         let measurement1 = NetworkTrafficMeasurement {
             precise_time_ns: 60_000_000_000,
             interfaces: interfaces1,
         };
 
         let mut interfaces2 = Interfaces::new();
-        interfaces2.insert(
-            "eth0".to_string(),
-            NetworkTraffic {
-                received: 2000,
-                transmitted: 2600,
-            },
-        );
+        interfaces2.insert("eth0".to_string(), helpers::network_traffic(2000, 2600));
         let measurement2 = NetworkTrafficMeasurement {
             precise_time_ns: 120_000_000_000,
             interfaces: interfaces2,
         };
 
-        match measurement1.calculate_per_minute(&measurement2) {
-            Err(ProbeError::UnexpectedContent(_)) => (),
-            r => panic!("Unexpected result: {:?}", r),
+        let per_minute = measurement1.calculate_per_minute(&measurement2).unwrap();
+        assert!(per_minute.interfaces.is_empty());
+        assert_eq!(vec!["eth0".to_owned()], per_minute.added);
+        assert_eq!(vec!["eth1".to_owned()], per_minute.removed);
+        assert!(per_minute.reset.is_empty());
+    }
+
+    #[test]
+    fn test_calculate_per_minute_partial_interfaces() {
+        let mut interfaces1 = Interfaces::new();
+        interfaces1.insert("eth0".to_string(), helpers::network_traffic(1000, 1000));
+        interfaces1.insert("veth1".to_string(), helpers::network_traffic(1000, 1000));
+        interfaces1.insert("veth2".to_string(), helpers::network_traffic(5000, 5000));
+        let measurement1 = NetworkTrafficMeasurement {
+            precise_time_ns: 60_000_000_000,
+            interfaces: interfaces1,
+        };
+
+        let mut interfaces2 = Interfaces::new();
+        interfaces2.insert("eth0".to_string(), helpers::network_traffic(2000, 2600));
+        interfaces2.insert("veth2".to_string(), helpers::network_traffic(100, 100));
+        interfaces2.insert("veth3".to_string(), helpers::network_traffic(100, 100));
+        interfaces2.insert("veth4".to_string(), helpers::network_traffic(100, 100));
+        let measurement2 = NetworkTrafficMeasurement {
+            precise_time_ns: 120_000_000_000,
+            interfaces: interfaces2,
+        };
+
+        let per_minute = measurement1.calculate_per_minute(&measurement2).unwrap();
+        assert_eq!(1, per_minute.interfaces.len());
+        let eth0 = per_minute.interfaces.get("eth0").unwrap();
+        assert_eq!(1000, eth0.received);
+        assert_eq!(1600, eth0.transmitted);
+        assert_eq!(
+            vec!["veth3".to_owned(), "veth4".to_owned()],
+            per_minute.added
+        );
+        assert_eq!(vec!["veth1".to_owned()], per_minute.removed);
+        assert_eq!(vec!["veth2".to_owned()], per_minute.reset);
+    }
+
+    #[test]
+    fn test_link_utilization() {
+        // 1 Gbit/s is 7.5 GB per minute
+        let traffic = helpers::network_traffic(750_000_000, 3_750_000_000);
+
+        let utilization = traffic.link_utilization(1000).unwrap();
+        assert!(utilization.received > 9.9);
+        assert!(utilization.received <= 10.1);
+        assert!(utilization.transmitted > 49.9);
+        assert!(utilization.transmitted <= 50.1);
+        assert_eq!(None, traffic.link_utilization(0));
+    }
+
+    mod helpers {
+        use super::super::NetworkTraffic;
+
+        pub fn network_traffic(received: u64, transmitted: u64) -> NetworkTraffic {
+            NetworkTraffic {
+                received,
+                received_packets: received,
+                received_errors: received,
+                received_dropped: received,
+                received_fifo_errors: received,
+                received_frame_errors: received,
+                received_compressed: received,
+                received_multicast: received,
+                transmitted,
+                transmitted_packets: transmitted,
+                transmitted_errors: transmitted,
+                transmitted_dropped: transmitted,
+                transmitted_fifo_errors: transmitted,
+                transmitted_collisions: transmitted,
+                transmitted_carrier_errors: transmitted,
+                transmitted_compressed: transmitted,
+            }
         }
     }
 }
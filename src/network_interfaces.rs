@@ -0,0 +1,307 @@
+use super::Result;
+use std::collections::HashMap;
+
+pub type NetworkInterfaces = HashMap<String, NetworkInterface>;
+
+/// Operational state of an interface as reported in `/sys/class/net/<interface>/operstate`
+#[derive(Debug, PartialEq, Clone)]
+pub enum OperState {
+    Up,
+    Down,
+    Dormant,
+    LowerLayerDown,
+    NotPresent,
+    Testing,
+    /// Reported by interfaces that don't track their state, such as the loopback interface
+    Unknown,
+    /// State not known to this crate, as reported by the kernel
+    Other(String),
+}
+
+impl OperState {
+    fn from_str(state: &str) -> OperState {
+        match state {
+            "up" => OperState::Up,
+            "down" => OperState::Down,
+            "dormant" => OperState::Dormant,
+            "lowerlayerdown" => OperState::LowerLayerDown,
+            "notpresent" => OperState::NotPresent,
+            "testing" => OperState::Testing,
+            "unknown" => OperState::Unknown,
+            other => OperState::Other(other.to_owned()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Duplex {
+    Full,
+    Half,
+}
+
+/// Metadata of a network interface from `/sys/class/net/<interface>`
+#[derive(Debug, PartialEq)]
+pub struct NetworkInterface {
+    pub name: String,
+    pub operstate: OperState,
+    /// Whether the physical link is up, `None` when the interface is down
+    pub carrier: Option<bool>,
+    /// Link speed in Mbit/s, `None` when unknown or not applicable
+    pub speed: Option<u64>,
+    /// `None` when unknown or not applicable
+    pub duplex: Option<Duplex>,
+    pub mtu: u64,
+    /// Hardware address
+    pub address: String,
+    /// Hardware type (`ARPHRD_*`), for example 1 for ethernet and 772 for loopback
+    pub interface_type: u16,
+    /// Whether the interface is not backed by a device, such as bridges, veth pairs and loopback
+    pub is_virtual: bool,
+    pub is_bond: bool,
+    pub is_bridge: bool,
+    /// Bond or bridge this interface is a member of
+    pub master: Option<String>,
+    /// Interfaces that are a member of this bond or bridge
+    pub members: Vec<String>,
+}
+
+/// Read the metadata of all network interfaces. Interfaces that are removed while reading are
+/// skipped.
+#[cfg(target_os = "linux")]
+pub fn read() -> Result<NetworkInterfaces> {
+    os::read()
+}
+
+#[cfg(target_os = "linux")]
+mod os {
+    use super::super::{file_to_string, parse_u64, path_to_string, ProbeError, Result};
+    use super::{Duplex, NetworkInterface, NetworkInterfaces, OperState};
+    use std::fs;
+    use std::io;
+    use std::path::Path;
+
+    #[inline]
+    pub fn read() -> Result<NetworkInterfaces> {
+        read_and_parse_interfaces(
+            Path::new("/sys/class/net"),
+            Path::new("/sys/devices/virtual/net"),
+        )
+    }
+
+    pub fn read_and_parse_interfaces(
+        class_dir: &Path,
+        virtual_dir: &Path,
+    ) -> Result<NetworkInterfaces> {
+        let entries =
+            fs::read_dir(class_dir).map_err(|e| ProbeError::IO(e, path_to_string(class_dir)))?;
+
+        let mut interfaces = NetworkInterfaces::new();
+        for entry in entries {
+            let entry = entry.map_err(|e| ProbeError::IO(e, path_to_string(class_dir)))?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+
+            match read_and_parse_interface(&entry.path(), &name, virtual_dir) {
+                Ok(interface) => {
+                    interfaces.insert(name, interface);
+                }
+                Err(ProbeError::IO(ref e, _)) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(interfaces)
+    }
+
+    pub fn read_and_parse_interface(
+        dir: &Path,
+        name: &str,
+        virtual_dir: &Path,
+    ) -> Result<NetworkInterface> {
+        let carrier = match read_optional_value(&dir.join("carrier"))? {
+            Some(carrier) => Some(parse_u64(&carrier)? == 1),
+            None => None,
+        };
+        // The speed is -1 when unknown
+        let speed = match read_optional_value(&dir.join("speed"))? {
+            Some(ref speed) if speed.starts_with('-') => None,
+            Some(speed) => Some(parse_u64(&speed)?),
+            None => None,
+        };
+        let duplex = match read_optional_value(&dir.join("duplex"))?.as_deref() {
+            Some("full") => Some(Duplex::Full),
+            Some("half") => Some(Duplex::Half),
+            _ => None,
+        };
+        let interface_type = read_value(&dir.join("type"))?;
+        let is_bond = dir.join("bonding").is_dir();
+        let is_bridge = dir.join("bridge").is_dir();
+
+        let members = if is_bond {
+            read_value(&dir.join("bonding/slaves"))?
+                .split_whitespace()
+                .map(|member| member.to_owned())
+                .collect()
+        } else if is_bridge {
+            read_dir_names(&dir.join("brif"))?
+        } else {
+            Vec::new()
+        };
+
+        Ok(NetworkInterface {
+            name: name.to_owned(),
+            operstate: OperState::from_str(&read_value(&dir.join("operstate"))?),
+            carrier,
+            speed,
+            duplex,
+            mtu: parse_u64(&read_value(&dir.join("mtu"))?)?,
+            address: read_value(&dir.join("address"))?,
+            interface_type: interface_type.parse().map_err(|_| {
+                ProbeError::UnexpectedContent(format!(
+                    "Could not parse '{}' as interface type",
+                    interface_type
+                ))
+            })?,
+            is_virtual: virtual_dir.join(name).is_dir(),
+            is_bond,
+            is_bridge,
+            master: read_master(&dir.join("master"))?,
+            members,
+        })
+    }
+
+    fn read_value(path: &Path) -> Result<String> {
+        Ok(file_to_string(path)?.trim().to_owned())
+    }
+
+    // Some attributes can't be read when the interface is down or doesn't support them.
+    fn read_optional_value(path: &Path) -> Result<Option<String>> {
+        match read_value(path) {
+            Ok(value) => Ok(Some(value)),
+            Err(ProbeError::IO(ref e, _))
+                if e.kind() == io::ErrorKind::NotFound
+                    || e.raw_os_error() == Some(libc::EINVAL) =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    // The master is a symlink to the directory of the bond or bridge.
+    fn read_master(path: &Path) -> Result<Option<String>> {
+        match fs::read_link(path) {
+            Ok(target) => Ok(target
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(ProbeError::IO(e, path_to_string(path))),
+        }
+    }
+
+    fn read_dir_names(path: &Path) -> Result<Vec<String>> {
+        let entries = fs::read_dir(path).map_err(|e| ProbeError::IO(e, path_to_string(path)))?;
+
+        let mut names = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|e| ProbeError::IO(e, path_to_string(path)))?;
+            names.push(entry.file_name().to_string_lossy().into_owned());
+        }
+        names.sort();
+        Ok(names)
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+    use super::super::ProbeError;
+    use super::os::read_and_parse_interfaces;
+    use super::{Duplex, NetworkInterface, OperState};
+    use std::path::Path;
+
+    #[test]
+    fn test_read() {
+        let interfaces = super::read().unwrap();
+        let lo = interfaces.get("lo").unwrap();
+        assert!(lo.is_virtual);
+        assert_eq!(772, lo.interface_type);
+    }
+
+    #[test]
+    fn test_read_and_parse_interfaces() {
+        let interfaces = read_and_parse_interfaces(
+            Path::new("fixtures/linux/sys/class/net"),
+            Path::new("fixtures/linux/sys/devices/virtual/net"),
+        )
+        .unwrap();
+
+        assert_eq!(7, interfaces.len());
+        assert_eq!(
+            &NetworkInterface {
+                name: "eth0".to_owned(),
+                operstate: OperState::Up,
+                carrier: Some(true),
+                speed: Some(10000),
+                duplex: Some(Duplex::Full),
+                mtu: 1500,
+                address: "52:54:00:12:34:56".to_owned(),
+                interface_type: 1,
+                is_virtual: false,
+                is_bond: false,
+                is_bridge: false,
+                master: None,
+                members: vec![],
+            },
+            interfaces.get("eth0").unwrap()
+        );
+
+        let lo = interfaces.get("lo").unwrap();
+        assert_eq!(OperState::Unknown, lo.operstate);
+        assert_eq!(None, lo.speed);
+        assert_eq!(None, lo.duplex);
+        assert!(lo.is_virtual);
+
+        let docker0 = interfaces.get("docker0").unwrap();
+        assert!(docker0.is_virtual);
+        assert!(docker0.is_bridge);
+        assert_eq!(vec!["veth1a2b3c4".to_owned()], docker0.members);
+
+        let veth = interfaces.get("veth1a2b3c4").unwrap();
+        assert!(veth.is_virtual);
+        assert_eq!(Some("docker0".to_owned()), veth.master);
+
+        let bond0 = interfaces.get("bond0").unwrap();
+        assert!(bond0.is_bond);
+        assert_eq!(Some(20000), bond0.speed);
+        assert_eq!(vec!["eth1".to_owned(), "eth2".to_owned()], bond0.members);
+
+        let eth1 = interfaces.get("eth1").unwrap();
+        assert!(!eth1.is_virtual);
+        assert_eq!(Some("bond0".to_owned()), eth1.master);
+
+        let eth2 = interfaces.get("eth2").unwrap();
+        assert_eq!(OperState::LowerLayerDown, eth2.operstate);
+        assert_eq!(Some(false), eth2.carrier);
+        assert_eq!(None, eth2.speed);
+        assert_eq!(None, eth2.duplex);
+    }
+
+    #[test]
+    fn test_read_and_parse_interfaces_wrong_path() {
+        match read_and_parse_interfaces(Path::new("/nonsense"), Path::new("/nonsense")) {
+            Err(ProbeError::IO(_, _)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_interfaces_garbage() {
+        match read_and_parse_interfaces(
+            Path::new("fixtures/linux/sys/class/net_garbage"),
+            Path::new("fixtures/linux/sys/devices/virtual/net"),
+        ) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+}
@@ -0,0 +1,363 @@
+use super::{calculate_time_difference, time_adjusted, ProbeError, Result};
+use std::collections::HashMap;
+
+/// Fields by name per protocol, such as `Tcp` or `TcpExt`
+pub type ProtocolFields = HashMap<String, HashMap<String, i64>>;
+
+/// Measurement of the protocol statistics in `/proc/net/snmp` and `/proc/net/netstat` at a
+/// certain time
+#[derive(Debug, PartialEq)]
+pub struct ProtocolStatsMeasurement {
+    pub precise_time_ns: u64,
+    pub protocols: ProtocolFields,
+}
+
+impl ProtocolStatsMeasurement {
+    /// Value of a field of a protocol, for example `get("Tcp", "RetransSegs")`
+    pub fn get(&self, protocol: &str, field: &str) -> Option<i64> {
+        self.protocols
+            .get(protocol)
+            .and_then(|fields| fields.get(field))
+            .copied()
+    }
+
+    /// The most important counters of the TCP and UDP protocols
+    pub fn counters(&self) -> Result<ProtocolCounters> {
+        let counter = |protocol: &str, field: &str| -> Result<u64> {
+            match self.get(protocol, field) {
+                Some(value) if value >= 0 => Ok(value as u64),
+                Some(value) => Err(ProbeError::UnexpectedContent(format!(
+                    "Negative value {} for '{} {}'",
+                    value, protocol, field
+                ))),
+                None => Err(ProbeError::UnexpectedContent(format!(
+                    "Field '{} {}' not found",
+                    protocol, field
+                ))),
+            }
+        };
+
+        Ok(ProtocolCounters {
+            tcp_retransmitted_segments: counter("Tcp", "RetransSegs")?,
+            tcp_attempt_fails: counter("Tcp", "AttemptFails")?,
+            tcp_established_resets: counter("Tcp", "EstabResets")?,
+            tcp_out_resets: counter("Tcp", "OutRsts")?,
+            tcp_listen_overflows: counter("TcpExt", "ListenOverflows")?,
+            tcp_listen_drops: counter("TcpExt", "ListenDrops")?,
+            udp_receive_buffer_errors: counter("Udp", "RcvbufErrors")?,
+            udp_send_buffer_errors: counter("Udp", "SndbufErrors")?,
+        })
+    }
+
+    /// Calculate the most important counters per minute based on this measurement and a
+    /// measurement in the future. It is advisable to make the next measurement roughly a minute
+    /// from this one for the most reliable result.
+    pub fn calculate_per_minute(
+        &self,
+        next_measurement: &ProtocolStatsMeasurement,
+    ) -> Result<ProtocolCounters> {
+        let time_difference =
+            calculate_time_difference(self.precise_time_ns, next_measurement.precise_time_ns)?;
+        let counters = self.counters()?;
+        let next_counters = next_measurement.counters()?;
+
+        Ok(ProtocolCounters {
+            tcp_retransmitted_segments: time_adjusted(
+                "tcp_retransmitted_segments",
+                next_counters.tcp_retransmitted_segments,
+                counters.tcp_retransmitted_segments,
+                time_difference,
+            )?,
+            tcp_attempt_fails: time_adjusted(
+                "tcp_attempt_fails",
+                next_counters.tcp_attempt_fails,
+                counters.tcp_attempt_fails,
+                time_difference,
+            )?,
+            tcp_established_resets: time_adjusted(
+                "tcp_established_resets",
+                next_counters.tcp_established_resets,
+                counters.tcp_established_resets,
+                time_difference,
+            )?,
+            tcp_out_resets: time_adjusted(
+                "tcp_out_resets",
+                next_counters.tcp_out_resets,
+                counters.tcp_out_resets,
+                time_difference,
+            )?,
+            tcp_listen_overflows: time_adjusted(
+                "tcp_listen_overflows",
+                next_counters.tcp_listen_overflows,
+                counters.tcp_listen_overflows,
+                time_difference,
+            )?,
+            tcp_listen_drops: time_adjusted(
+                "tcp_listen_drops",
+                next_counters.tcp_listen_drops,
+                counters.tcp_listen_drops,
+                time_difference,
+            )?,
+            udp_receive_buffer_errors: time_adjusted(
+                "udp_receive_buffer_errors",
+                next_counters.udp_receive_buffer_errors,
+                counters.udp_receive_buffer_errors,
+                time_difference,
+            )?,
+            udp_send_buffer_errors: time_adjusted(
+                "udp_send_buffer_errors",
+                next_counters.udp_send_buffer_errors,
+                counters.udp_send_buffer_errors,
+                time_difference,
+            )?,
+        })
+    }
+}
+
+/// The most important TCP and UDP counters
+#[derive(Debug, PartialEq)]
+pub struct ProtocolCounters {
+    /// Segments retransmitted, `Tcp RetransSegs`
+    pub tcp_retransmitted_segments: u64,
+    /// Failed connection attempts, `Tcp AttemptFails`
+    pub tcp_attempt_fails: u64,
+    /// Established connections that were reset, `Tcp EstabResets`
+    pub tcp_established_resets: u64,
+    /// Segments sent with the RST flag, `Tcp OutRsts`
+    pub tcp_out_resets: u64,
+    /// Connections dropped because the accept queue was full, `TcpExt ListenOverflows`
+    pub tcp_listen_overflows: u64,
+    /// Connections dropped on a listening socket for any reason, `TcpExt ListenDrops`
+    pub tcp_listen_drops: u64,
+    /// Datagrams dropped because the receive buffer was full, `Udp RcvbufErrors`
+    pub udp_receive_buffer_errors: u64,
+    /// Datagrams dropped because the send buffer was full, `Udp SndbufErrors`
+    pub udp_send_buffer_errors: u64,
+}
+
+/// Read the protocol statistics of the system.
+#[cfg(target_os = "linux")]
+pub fn read() -> Result<ProtocolStatsMeasurement> {
+    os::read()
+}
+
+#[cfg(target_os = "linux")]
+mod os {
+    use super::super::{file_to_string, parse_i64, precise_time_ns, ProbeError, Result};
+    use super::{ProtocolFields, ProtocolStatsMeasurement};
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    #[inline]
+    pub fn read() -> Result<ProtocolStatsMeasurement> {
+        read_and_parse_protocol_stats(&[
+            Path::new("/proc/net/snmp"),
+            Path::new("/proc/net/netstat"),
+        ])
+    }
+
+    pub fn read_and_parse_protocol_stats(paths: &[&Path]) -> Result<ProtocolStatsMeasurement> {
+        let precise_time_ns = precise_time_ns();
+
+        let mut protocols = ProtocolFields::new();
+        for path in paths {
+            parse_header_value_lines(&file_to_string(path)?, &mut protocols)?;
+        }
+
+        Ok(ProtocolStatsMeasurement {
+            precise_time_ns,
+            protocols,
+        })
+    }
+
+    // Every protocol has a line with the field names followed by a line with the values:
+    //   Tcp: RtoAlgorithm RtoMin ...
+    //   Tcp: 1 200 ...
+    pub fn parse_header_value_lines(raw_data: &str, protocols: &mut ProtocolFields) -> Result<()> {
+        let mut lines = raw_data.lines().filter(|line| !line.trim().is_empty());
+        while let Some(header_line) = lines.next() {
+            let header: Vec<&str> = header_line.split_whitespace().collect();
+            let values: Vec<&str> = match lines.next() {
+                Some(value_line) => value_line.split_whitespace().collect(),
+                None => {
+                    return Err(ProbeError::UnexpectedContent(format!(
+                        "No values for '{}'",
+                        header_line
+                    )))
+                }
+            };
+
+            if header.is_empty() || header[0] != values[0] || header.len() != values.len() {
+                return Err(ProbeError::UnexpectedContent(format!(
+                    "Values do not match header '{}'",
+                    header_line
+                )));
+            }
+
+            let mut fields = HashMap::new();
+            for (name, value) in header[1..].iter().zip(values[1..].iter()) {
+                fields.insert((*name).to_owned(), parse_i64(value)?);
+            }
+            protocols.insert(header[0].trim_end_matches(':').to_owned(), fields);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+    use super::super::ProbeError;
+    use super::os::read_and_parse_protocol_stats;
+    use super::{ProtocolCounters, ProtocolFields, ProtocolStatsMeasurement};
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    #[test]
+    fn test_read() {
+        let measurement = super::read().unwrap();
+        assert!(measurement.get("Tcp", "RetransSegs").is_some());
+        assert!(measurement.counters().is_ok());
+    }
+
+    #[test]
+    fn test_read_and_parse_protocol_stats() {
+        let measurement = read_and_parse_protocol_stats(&[
+            Path::new("fixtures/linux/network_protocols/proc_net_snmp"),
+            Path::new("fixtures/linux/network_protocols/proc_net_netstat"),
+        ])
+        .unwrap();
+
+        assert_eq!(8, measurement.protocols.len());
+        assert_eq!(Some(-1), measurement.get("Tcp", "MaxConn"));
+        assert_eq!(Some(12), measurement.get("TcpExt", "TW"));
+        assert_eq!(Some(177718502), measurement.get("IpExt", "InOctets"));
+        assert_eq!(None, measurement.get("Tcp", "Nonsense"));
+        assert_eq!(None, measurement.get("Nonsense", "InOctets"));
+
+        let expected = ProtocolCounters {
+            tcp_retransmitted_segments: 1731,
+            tcp_attempt_fails: 52,
+            tcp_established_resets: 18,
+            tcp_out_resets: 254,
+            tcp_listen_overflows: 7,
+            tcp_listen_drops: 9,
+            udp_receive_buffer_errors: 31,
+            udp_send_buffer_errors: 2,
+        };
+        assert_eq!(expected, measurement.counters().unwrap());
+    }
+
+    #[test]
+    fn test_read_and_parse_protocol_stats_wrong_path() {
+        match read_and_parse_protocol_stats(&[Path::new("/nonsense")]) {
+            Err(ProbeError::IO(_, _)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_protocol_stats_incomplete() {
+        let path = Path::new("fixtures/linux/network_protocols/proc_net_snmp_incomplete");
+        match read_and_parse_protocol_stats(&[path]) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_protocol_stats_garbage() {
+        let path = Path::new("fixtures/linux/network_protocols/proc_net_snmp_garbage");
+        match read_and_parse_protocol_stats(&[path]) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_counters_missing_field() {
+        let measurement = ProtocolStatsMeasurement {
+            precise_time_ns: 60_000_000_000,
+            protocols: ProtocolFields::new(),
+        };
+
+        match measurement.counters() {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_calculate_per_minute_partial_minute() {
+        let measurement1 = ProtocolStatsMeasurement {
+            precise_time_ns: 60_000_000_000,
+            protocols: helpers::protocol_fields(100),
+        };
+        let measurement2 = ProtocolStatsMeasurement {
+            precise_time_ns: 90_000_000_000,
+            protocols: helpers::protocol_fields(150),
+        };
+
+        let per_minute = measurement1.calculate_per_minute(&measurement2).unwrap();
+        assert_eq!(100, per_minute.tcp_retransmitted_segments);
+        assert_eq!(100, per_minute.tcp_listen_overflows);
+        assert_eq!(100, per_minute.udp_receive_buffer_errors);
+    }
+
+    #[test]
+    fn test_calculate_per_minute_wrong_times() {
+        let measurement1 = ProtocolStatsMeasurement {
+            precise_time_ns: 90_000_000_000,
+            protocols: helpers::protocol_fields(0),
+        };
+        let measurement2 = ProtocolStatsMeasurement {
+            precise_time_ns: 60_000_000_000,
+            protocols: helpers::protocol_fields(0),
+        };
+
+        match measurement1.calculate_per_minute(&measurement2) {
+            Err(ProbeError::InvalidInput(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_calculate_per_minute_values_lower() {
+        let measurement1 = ProtocolStatsMeasurement {
+            precise_time_ns: 60_000_000_000,
+            protocols: helpers::protocol_fields(100),
+        };
+        let measurement2 = ProtocolStatsMeasurement {
+            precise_time_ns: 90_000_000_000,
+            protocols: helpers::protocol_fields(50),
+        };
+
+        match measurement1.calculate_per_minute(&measurement2) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    mod helpers {
+        use super::super::ProtocolFields;
+        use super::HashMap;
+
+        pub fn protocol_fields(value: i64) -> ProtocolFields {
+            let mut protocols = ProtocolFields::new();
+            for (protocol, names) in &[
+                (
+                    "Tcp",
+                    vec!["RetransSegs", "AttemptFails", "EstabResets", "OutRsts"],
+                ),
+                ("TcpExt", vec!["ListenOverflows", "ListenDrops"]),
+                ("Udp", vec!["RcvbufErrors", "SndbufErrors"]),
+            ] {
+                let fields: HashMap<String, i64> =
+                    names.iter().map(|name| (name.to_string(), value)).collect();
+                protocols.insert(protocol.to_string(), fields);
+            }
+            protocols
+        }
+    }
+}
@@ -0,0 +1,438 @@
+use super::{calculate_time_difference, time_adjusted, Result};
+
+/// Measurement of pressure stall information at a certain time
+#[derive(Debug, PartialEq)]
+pub struct PressureMeasurement {
+    pub precise_time_ns: u64,
+    pub cpu: Pressure,
+    pub memory: Pressure,
+    pub io: Pressure,
+}
+
+impl PressureMeasurement {
+    /// Calculate the time tasks were stalled per minute based on this measurement and a
+    /// measurement in the future. It is advisable to make the next measurement roughly a minute
+    /// from this one for the most reliable result.
+    pub fn calculate_per_minute(
+        &self,
+        next_measurement: &PressureMeasurement,
+    ) -> Result<PressureStallPerMinute> {
+        let time_difference =
+            calculate_time_difference(self.precise_time_ns, next_measurement.precise_time_ns)?;
+
+        Ok(PressureStallPerMinute {
+            cpu: self
+                .cpu
+                .calculate_per_minute(&next_measurement.cpu, time_difference)?,
+            memory: self
+                .memory
+                .calculate_per_minute(&next_measurement.memory, time_difference)?,
+            io: self
+                .io
+                .calculate_per_minute(&next_measurement.io, time_difference)?,
+        })
+    }
+}
+
+/// Pressure stall information of a resource
+#[derive(Debug, PartialEq)]
+pub struct Pressure {
+    /// Share of time in which at least some tasks were stalled
+    pub some: PressureStat,
+    /// Share of time in which all non-idle tasks were stalled, not reported for cpu on kernels
+    /// before 5.13.
+    pub full: Option<PressureStat>,
+}
+
+impl Pressure {
+    fn calculate_per_minute(
+        &self,
+        next_pressure: &Pressure,
+        time_difference: u64,
+    ) -> Result<PressureStall> {
+        Ok(PressureStall {
+            some_us: time_adjusted(
+                "some",
+                next_pressure.some.total_us,
+                self.some.total_us,
+                time_difference,
+            )?,
+            full_us: match (&self.full, &next_pressure.full) {
+                (Some(full), Some(next_full)) => Some(time_adjusted(
+                    "full",
+                    next_full.total_us,
+                    full.total_us,
+                    time_difference,
+                )?),
+                _ => None,
+            },
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PressureStat {
+    /// Percentage of stalled time over the last 10 seconds
+    pub avg10: f32,
+    /// Percentage of stalled time over the last 60 seconds
+    pub avg60: f32,
+    /// Percentage of stalled time over the last 300 seconds
+    pub avg300: f32,
+    /// Total stalled time in microseconds
+    pub total_us: u64,
+}
+
+/// Time stalled in microseconds for a minute
+#[derive(Debug, PartialEq)]
+pub struct PressureStall {
+    pub some_us: u64,
+    pub full_us: Option<u64>,
+}
+
+/// Time stalled for a minute, calculated based on two measurements.
+#[derive(Debug, PartialEq)]
+pub struct PressureStallPerMinute {
+    pub cpu: PressureStall,
+    pub memory: PressureStall,
+    pub io: PressureStall,
+}
+
+/// Read the current pressure stall information of the system.
+#[cfg(target_os = "linux")]
+pub fn read() -> Result<PressureMeasurement> {
+    os::read()
+}
+
+/// Read the current pressure stall information of the container.
+#[cfg(target_os = "linux")]
+pub fn read_cgroup() -> Result<PressureMeasurement> {
+    os::read_cgroup(&super::cgroup::read()?)
+}
+
+/// Read the current pressure stall information of the cgroup of a process with given pid.
+#[cfg(target_os = "linux")]
+pub fn read_cgroup_of(pid: libc::pid_t) -> Result<PressureMeasurement> {
+    os::read_cgroup(&super::cgroup::read_of(pid)?)
+}
+
+#[cfg(target_os = "linux")]
+mod os {
+    use super::super::cgroup::Cgroups;
+    use super::super::{
+        file_to_buf_reader, parse_u64, path_to_string, precise_time_ns, ProbeError, Result,
+    };
+    use super::{Pressure, PressureMeasurement, PressureStat};
+    use std::io::BufRead;
+    use std::path::Path;
+
+    #[inline]
+    pub fn read() -> Result<PressureMeasurement> {
+        read_and_parse_pressure(
+            Path::new("/proc/pressure/cpu"),
+            Path::new("/proc/pressure/memory"),
+            Path::new("/proc/pressure/io"),
+        )
+    }
+
+    #[inline]
+    pub fn read_cgroup(cgroups: &Cgroups) -> Result<PressureMeasurement> {
+        // Pressure stall information is only available for the cgroup v2 hierarchy.
+        let dir = cgroups.unified_dir().ok_or_else(|| {
+            ProbeError::UnexpectedContent("Process is not in a cgroup v2 hierarchy".to_owned())
+        })?;
+
+        read_and_parse_pressure(
+            &dir.join("cpu.pressure"),
+            &dir.join("memory.pressure"),
+            &dir.join("io.pressure"),
+        )
+    }
+
+    pub fn read_and_parse_pressure(
+        cpu_path: &Path,
+        memory_path: &Path,
+        io_path: &Path,
+    ) -> Result<PressureMeasurement> {
+        let precise_time_ns = precise_time_ns();
+
+        Ok(PressureMeasurement {
+            precise_time_ns,
+            cpu: read_and_parse_pressure_file(cpu_path)?,
+            memory: read_and_parse_pressure_file(memory_path)?,
+            io: read_and_parse_pressure_file(io_path)?,
+        })
+    }
+
+    pub fn read_and_parse_pressure_file(path: &Path) -> Result<Pressure> {
+        let reader = file_to_buf_reader(path)?;
+
+        let mut some = None;
+        let mut full = None;
+        for line_result in reader.lines() {
+            let line = line_result.map_err(|e| ProbeError::IO(e, path_to_string(path)))?;
+            let segments: Vec<&str> = line.split_whitespace().collect();
+            if segments.is_empty() {
+                continue;
+            }
+
+            match segments[0] {
+                "some" => some = Some(parse_pressure_stat(&segments[1..])?),
+                "full" => full = Some(parse_pressure_stat(&segments[1..])?),
+                _ => {
+                    return Err(ProbeError::UnexpectedContent(format!(
+                        "Unexpected line '{}'",
+                        line
+                    )))
+                }
+            }
+        }
+
+        match some {
+            Some(some) => Ok(Pressure { some, full }),
+            None => Err(ProbeError::UnexpectedContent(
+                "Did not encounter the some line".to_owned(),
+            )),
+        }
+    }
+
+    // columns: avg10=<pct> avg60=<pct> avg300=<pct> total=<us>
+    fn parse_pressure_stat(segments: &[&str]) -> Result<PressureStat> {
+        let mut stat = PressureStat {
+            avg10: 0.0,
+            avg60: 0.0,
+            avg300: 0.0,
+            total_us: 0,
+        };
+
+        let mut fields_encountered = 0;
+        for segment in segments {
+            let (key, value) = segment.split_once('=').ok_or_else(|| {
+                ProbeError::UnexpectedContent(format!("Could not parse '{}'", segment))
+            })?;
+            fields_encountered += match key {
+                "avg10" => {
+                    stat.avg10 = parse_f32(value)?;
+                    1
+                }
+                "avg60" => {
+                    stat.avg60 = parse_f32(value)?;
+                    1
+                }
+                "avg300" => {
+                    stat.avg300 = parse_f32(value)?;
+                    1
+                }
+                "total" => {
+                    stat.total_us = parse_u64(value)?;
+                    1
+                }
+                _ => 0,
+            };
+        }
+
+        if fields_encountered != 4 {
+            return Err(ProbeError::UnexpectedContent(
+                "Did not encounter all expected fields".to_owned(),
+            ));
+        }
+        Ok(stat)
+    }
+
+    #[inline]
+    fn parse_f32(segment: &str) -> Result<f32> {
+        segment.parse().map_err(|_| {
+            ProbeError::UnexpectedContent(format!("Could not parse '{}' as f32", segment))
+        })
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+    use super::os::{read_and_parse_pressure, read_and_parse_pressure_file};
+    use super::{Pressure, PressureMeasurement, PressureStall, PressureStat};
+    use crate::ProbeError;
+    use std::path::Path;
+
+    #[test]
+    fn test_read_pressure() {
+        assert!(super::read().is_ok());
+    }
+
+    #[test]
+    fn test_read_and_parse_pressure_file() {
+        let path = Path::new("fixtures/linux/pressure/memory");
+        let pressure = read_and_parse_pressure_file(path).unwrap();
+
+        let expected = Pressure {
+            some: PressureStat {
+                avg10: 1.25,
+                avg60: 0.5,
+                avg300: 0.12,
+                total_us: 1523872,
+            },
+            full: Some(PressureStat {
+                avg10: 0.75,
+                avg60: 0.25,
+                avg300: 0.06,
+                total_us: 1233953,
+            }),
+        };
+        assert_eq!(expected, pressure);
+    }
+
+    #[test]
+    fn test_read_and_parse_pressure_file_without_full() {
+        let path = Path::new("fixtures/linux/pressure/cpu_some_only");
+        let pressure = read_and_parse_pressure_file(path).unwrap();
+
+        assert_eq!(14409836, pressure.some.total_us);
+        assert_eq!(None, pressure.full);
+    }
+
+    #[test]
+    fn test_read_and_parse_pressure_wrong_path() {
+        match read_and_parse_pressure_file(Path::new("/nonsense")) {
+            Err(ProbeError::IO(_, _)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_pressure_incomplete() {
+        let path = Path::new("fixtures/linux/pressure/incomplete");
+        match read_and_parse_pressure_file(path) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_pressure_garbage() {
+        let path = Path::new("fixtures/linux/pressure/garbage");
+        match read_and_parse_pressure_file(path) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_cgroup_pressure() {
+        let dir = Path::new("fixtures/linux/sys/fs/cgroup_v2/pressure");
+        let measurement = read_and_parse_pressure(
+            &dir.join("cpu.pressure"),
+            &dir.join("memory.pressure"),
+            &dir.join("io.pressure"),
+        )
+        .unwrap();
+
+        assert_eq!(2410327, measurement.cpu.some.total_us);
+        assert_eq!(
+            Some(1942117),
+            measurement.cpu.full.map(|full| full.total_us)
+        );
+        assert_eq!(0, measurement.memory.some.total_us);
+        assert_eq!(88314, measurement.io.some.total_us);
+    }
+
+    #[test]
+    fn test_calculate_per_minute_partial_minute() {
+        let measurement1 = PressureMeasurement {
+            precise_time_ns: 60_000_000_000,
+            cpu: helpers::pressure(1_000_000, None),
+            memory: helpers::pressure(1_000_000, Some(500_000)),
+            io: helpers::pressure(1_000_000, Some(500_000)),
+        };
+        let measurement2 = PressureMeasurement {
+            precise_time_ns: 90_000_000_000,
+            cpu: helpers::pressure(1_500_000, Some(0)),
+            memory: helpers::pressure(1_100_000, Some(510_000)),
+            io: helpers::pressure(1_000_000, Some(500_000)),
+        };
+
+        let per_minute = measurement1.calculate_per_minute(&measurement2).unwrap();
+        assert_eq!(
+            PressureStall {
+                some_us: 1_000_000,
+                full_us: None,
+            },
+            per_minute.cpu
+        );
+        assert_eq!(
+            PressureStall {
+                some_us: 200_000,
+                full_us: Some(20_000),
+            },
+            per_minute.memory
+        );
+        assert_eq!(
+            PressureStall {
+                some_us: 0,
+                full_us: Some(0),
+            },
+            per_minute.io
+        );
+    }
+
+    #[test]
+    fn test_calculate_per_minute_wrong_times() {
+        let measurement1 = PressureMeasurement {
+            precise_time_ns: 90_000_000_000,
+            cpu: helpers::pressure(0, None),
+            memory: helpers::pressure(0, None),
+            io: helpers::pressure(0, None),
+        };
+        let measurement2 = PressureMeasurement {
+            precise_time_ns: 60_000_000_000,
+            cpu: helpers::pressure(0, None),
+            memory: helpers::pressure(0, None),
+            io: helpers::pressure(0, None),
+        };
+
+        match measurement1.calculate_per_minute(&measurement2) {
+            Err(ProbeError::InvalidInput(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_calculate_per_minute_values_lower() {
+        let measurement1 = PressureMeasurement {
+            precise_time_ns: 60_000_000_000,
+            cpu: helpers::pressure(2000, None),
+            memory: helpers::pressure(0, None),
+            io: helpers::pressure(0, None),
+        };
+        let measurement2 = PressureMeasurement {
+            precise_time_ns: 120_000_000_000,
+            cpu: helpers::pressure(1000, None),
+            memory: helpers::pressure(0, None),
+            io: helpers::pressure(0, None),
+        };
+
+        match measurement1.calculate_per_minute(&measurement2) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    mod helpers {
+        use super::super::{Pressure, PressureStat};
+
+        pub fn pressure(some_total_us: u64, full_total_us: Option<u64>) -> Pressure {
+            Pressure {
+                some: pressure_stat(some_total_us),
+                full: full_total_us.map(pressure_stat),
+            }
+        }
+
+        fn pressure_stat(total_us: u64) -> PressureStat {
+            PressureStat {
+                avg10: 0.0,
+                avg60: 0.0,
+                avg300: 0.0,
+                total_us,
+            }
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use super::{calculate_time_difference, time_adjusted, Result};
+
+/// Measurement of the I/O of a process at a certain time
+#[derive(Debug, PartialEq)]
+pub struct ProcessIoMeasurement {
+    pub precise_time_ns: u64,
+    pub io: ProcessIo,
+}
+
+impl ProcessIoMeasurement {
+    /// Calculate the I/O per minute based on this measurement and a measurement in the
+    /// future. It is advisable to make the next measurement roughly a minute from this one for the
+    /// most reliable result.
+    pub fn calculate_per_minute(
+        &self,
+        next_measurement: &ProcessIoMeasurement,
+    ) -> Result<ProcessIo> {
+        let time_difference =
+            calculate_time_difference(self.precise_time_ns, next_measurement.precise_time_ns)?;
+        let next_io = &next_measurement.io;
+
+        Ok(ProcessIo {
+            rchar: time_adjusted("rchar", next_io.rchar, self.io.rchar, time_difference)?,
+            wchar: time_adjusted("wchar", next_io.wchar, self.io.wchar, time_difference)?,
+            syscr: time_adjusted("syscr", next_io.syscr, self.io.syscr, time_difference)?,
+            syscw: time_adjusted("syscw", next_io.syscw, self.io.syscw, time_difference)?,
+            read_bytes: time_adjusted(
+                "read_bytes",
+                next_io.read_bytes,
+                self.io.read_bytes,
+                time_difference,
+            )?,
+            write_bytes: time_adjusted(
+                "write_bytes",
+                next_io.write_bytes,
+                self.io.write_bytes,
+                time_difference,
+            )?,
+            cancelled_write_bytes: time_adjusted(
+                "cancelled_write_bytes",
+                next_io.cancelled_write_bytes,
+                self.io.cancelled_write_bytes,
+                time_difference,
+            )?,
+        })
+    }
+}
+
+/// I/O counters of a process as reported in `/proc/<pid>/io`
+#[derive(Debug, PartialEq)]
+pub struct ProcessIo {
+    /// Bytes read, including reads from the page cache
+    pub rchar: u64,
+    /// Bytes written, including writes to the page cache
+    pub wchar: u64,
+    /// Number of read syscalls
+    pub syscr: u64,
+    /// Number of write syscalls
+    pub syscw: u64,
+    /// Bytes fetched from the storage layer
+    pub read_bytes: u64,
+    /// Bytes sent to the storage layer
+    pub write_bytes: u64,
+    /// Bytes written to the page cache that were truncated before reaching storage
+    pub cancelled_write_bytes: u64,
+}
+
+/// Read the I/O counters of this process.
+#[cfg(target_os = "linux")]
+pub fn read_self() -> Result<ProcessIoMeasurement> {
+    os::read_self()
+}
+
+/// Read the I/O counters of a process with given pid. Returns a `PermissionDenied` error for
+/// processes of other users.
+#[cfg(target_os = "linux")]
+pub fn read_of(pid: libc::pid_t) -> Result<ProcessIoMeasurement> {
+    os::read_of(pid)
+}
+
+#[cfg(target_os = "linux")]
+mod os {
+    use super::super::{parse_u64, path_to_string, precise_time_ns, ProbeError, Result};
+    use super::{ProcessIo, ProcessIoMeasurement};
+    use std::fs;
+    use std::io::{self, BufRead};
+    use std::path::Path;
+
+    const PROC_IO_NUMBER_OF_FIELDS: usize = 7;
+
+    #[inline]
+    pub fn read_self() -> Result<ProcessIoMeasurement> {
+        read_and_parse_proc_io(Path::new("/proc/self/io"))
+    }
+
+    #[inline]
+    pub fn read_of(pid: libc::pid_t) -> Result<ProcessIoMeasurement> {
+        read_and_parse_proc_io(Path::new(&format!("/proc/{}/io", pid)))
+    }
+
+    pub fn read_and_parse_proc_io(path: &Path) -> Result<ProcessIoMeasurement> {
+        let precise_time_ns = precise_time_ns();
+
+        // Access is checked when opening or when reading, depending on the kernel version.
+        let map_io_error = |e: io::Error| match e.kind() {
+            io::ErrorKind::PermissionDenied => ProbeError::PermissionDenied(path_to_string(path)),
+            _ => ProbeError::IO(e, path_to_string(path)),
+        };
+        let reader = fs::File::open(path)
+            .map(io::BufReader::new)
+            .map_err(map_io_error)?;
+
+        let mut io = ProcessIo {
+            rchar: 0,
+            wchar: 0,
+            syscr: 0,
+            syscw: 0,
+            read_bytes: 0,
+            write_bytes: 0,
+            cancelled_write_bytes: 0,
+        };
+
+        let mut fields_encountered = 0;
+        for line_result in reader.lines() {
+            let line = line_result.map_err(map_io_error)?;
+            let segments: Vec<&str> = line.split_whitespace().collect();
+            if segments.len() != 2 {
+                return Err(ProbeError::UnexpectedContent(format!(
+                    "Unexpected line '{}'",
+                    line
+                )));
+            }
+
+            let field = match segments[0] {
+                "rchar:" => &mut io.rchar,
+                "wchar:" => &mut io.wchar,
+                "syscr:" => &mut io.syscr,
+                "syscw:" => &mut io.syscw,
+                "read_bytes:" => &mut io.read_bytes,
+                "write_bytes:" => &mut io.write_bytes,
+                "cancelled_write_bytes:" => &mut io.cancelled_write_bytes,
+                _ => continue,
+            };
+            *field = parse_u64(segments[1])?;
+            fields_encountered += 1;
+        }
+
+        if fields_encountered != PROC_IO_NUMBER_OF_FIELDS {
+            return Err(ProbeError::UnexpectedContent(
+                "Did not encounter all expected fields".to_owned(),
+            ));
+        }
+
+        Ok(ProcessIoMeasurement {
+            precise_time_ns,
+            io,
+        })
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+    use super::super::ProbeError;
+    use super::os::read_and_parse_proc_io;
+    use super::{ProcessIo, ProcessIoMeasurement};
+    use std::path::Path;
+
+    #[test]
+    fn test_read_self() {
+        assert!(super::read_self().is_ok());
+    }
+
+    #[test]
+    fn test_read_of() {
+        let pid = unsafe { libc::getpid() };
+        assert!(super::read_of(pid).is_ok());
+    }
+
+    #[test]
+    fn test_read_of_invalid_pid() {
+        assert!(super::read_of(0).is_err());
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_io() {
+        let path = Path::new("fixtures/linux/process_io/proc_self_io");
+        let measurement = read_and_parse_proc_io(path).unwrap();
+
+        let expected = ProcessIo {
+            rchar: 3482910,
+            wchar: 1203344,
+            syscr: 4021,
+            syscw: 1388,
+            read_bytes: 524288,
+            write_bytes: 1126400,
+            cancelled_write_bytes: 4096,
+        };
+        assert_eq!(expected, measurement.io);
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_io_wrong_path() {
+        match read_and_parse_proc_io(Path::new("/nonsense")) {
+            Err(ProbeError::IO(_, _)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_io_incomplete() {
+        let path = Path::new("fixtures/linux/process_io/proc_self_io_incomplete");
+        match read_and_parse_proc_io(path) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_io_garbage() {
+        let path = Path::new("fixtures/linux/process_io/proc_self_io_garbage");
+        match read_and_parse_proc_io(path) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_calculate_per_minute_partial_minute() {
+        let measurement1 = ProcessIoMeasurement {
+            precise_time_ns: 60_000_000_000,
+            io: helpers::process_io(1000),
+        };
+        let measurement2 = ProcessIoMeasurement {
+            precise_time_ns: 90_000_000_000,
+            io: helpers::process_io(1500),
+        };
+
+        let per_minute = measurement1.calculate_per_minute(&measurement2).unwrap();
+        assert_eq!(helpers::process_io(1000), per_minute);
+    }
+
+    #[test]
+    fn test_calculate_per_minute_wrong_times() {
+        let measurement1 = ProcessIoMeasurement {
+            precise_time_ns: 90_000_000_000,
+            io: helpers::process_io(0),
+        };
+        let measurement2 = ProcessIoMeasurement {
+            precise_time_ns: 60_000_000_000,
+            io: helpers::process_io(0),
+        };
+
+        match measurement1.calculate_per_minute(&measurement2) {
+            Err(ProbeError::InvalidInput(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_calculate_per_minute_values_lower() {
+        let measurement1 = ProcessIoMeasurement {
+            precise_time_ns: 60_000_000_000,
+            io: helpers::process_io(1000),
+        };
+        let measurement2 = ProcessIoMeasurement {
+            precise_time_ns: 90_000_000_000,
+            io: helpers::process_io(500),
+        };
+
+        match measurement1.calculate_per_minute(&measurement2) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    mod helpers {
+        use super::super::ProcessIo;
+
+        pub fn process_io(value: u64) -> ProcessIo {
+            ProcessIo {
+                rchar: value,
+                wchar: value,
+                syscr: value,
+                syscw: value,
+                read_bytes: value,
+                write_bytes: value,
+                cancelled_write_bytes: value,
+            }
+        }
+    }
+}
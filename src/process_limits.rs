@@ -0,0 +1,247 @@
+use super::Result;
+
+/// Soft and hard value of a resource limit, `None` when unlimited.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Limit {
+    pub soft: Option<u64>,
+    pub hard: Option<u64>,
+}
+
+/// Resource limits of a process as reported in `/proc/<pid>/limits`. Units are the ones
+/// reported by the kernel: seconds, bytes or a count.
+#[derive(Debug, PartialEq)]
+pub struct ProcessLimits {
+    pub cpu_time: Limit,
+    pub file_size: Limit,
+    pub data_size: Limit,
+    pub stack_size: Limit,
+    pub core_file_size: Limit,
+    pub resident_set: Limit,
+    pub processes: Limit,
+    pub open_files: Limit,
+    pub locked_memory: Limit,
+    pub address_space: Limit,
+    pub file_locks: Limit,
+    pub pending_signals: Limit,
+    pub msgqueue_size: Limit,
+    pub nice_priority: Limit,
+    pub realtime_priority: Limit,
+    /// In microseconds
+    pub realtime_timeout: Limit,
+}
+
+/// Read the resource limits of this process.
+#[cfg(target_os = "linux")]
+pub fn read_self() -> Result<ProcessLimits> {
+    os::read_self()
+}
+
+/// Read the resource limits of a process with given pid.
+#[cfg(target_os = "linux")]
+pub fn read_of(pid: libc::pid_t) -> Result<ProcessLimits> {
+    os::read_of(pid)
+}
+
+#[cfg(target_os = "linux")]
+mod os {
+    use super::super::{file_to_buf_reader, parse_u64, path_to_string, ProbeError, Result};
+    use super::{Limit, ProcessLimits};
+    use std::io::BufRead;
+    use std::path::Path;
+
+    const PROC_LIMITS_NUMBER_OF_FIELDS: usize = 16;
+
+    #[inline]
+    pub fn read_self() -> Result<ProcessLimits> {
+        read_and_parse_proc_limits(Path::new("/proc/self/limits"))
+    }
+
+    #[inline]
+    pub fn read_of(pid: libc::pid_t) -> Result<ProcessLimits> {
+        read_and_parse_proc_limits(Path::new(&format!("/proc/{}/limits", pid)))
+    }
+
+    pub fn read_and_parse_proc_limits(path: &Path) -> Result<ProcessLimits> {
+        let unlimited = Limit {
+            soft: None,
+            hard: None,
+        };
+        let mut limits = ProcessLimits {
+            cpu_time: unlimited,
+            file_size: unlimited,
+            data_size: unlimited,
+            stack_size: unlimited,
+            core_file_size: unlimited,
+            resident_set: unlimited,
+            processes: unlimited,
+            open_files: unlimited,
+            locked_memory: unlimited,
+            address_space: unlimited,
+            file_locks: unlimited,
+            pending_signals: unlimited,
+            msgqueue_size: unlimited,
+            nice_priority: unlimited,
+            realtime_priority: unlimited,
+            realtime_timeout: unlimited,
+        };
+
+        let reader = file_to_buf_reader(path)?;
+
+        let mut fields_encountered = 0;
+        for line_result in reader.lines() {
+            let line = line_result.map_err(|e| ProbeError::IO(e, path_to_string(path)))?;
+
+            // columns: Limit Soft-Limit Hard-Limit Units
+            // The name of the limit contains spaces, it's padded to a width of 25 characters.
+            let (name, values) = match (line.get(..25), line.get(25..)) {
+                (Some(name), Some(values)) => (name.trim_end(), values),
+                _ => {
+                    return Err(ProbeError::UnexpectedContent(format!(
+                        "Unexpected line '{}'",
+                        line
+                    )))
+                }
+            };
+            let field = match name {
+                "Max cpu time" => &mut limits.cpu_time,
+                "Max file size" => &mut limits.file_size,
+                "Max data size" => &mut limits.data_size,
+                "Max stack size" => &mut limits.stack_size,
+                "Max core file size" => &mut limits.core_file_size,
+                "Max resident set" => &mut limits.resident_set,
+                "Max processes" => &mut limits.processes,
+                "Max open files" => &mut limits.open_files,
+                "Max locked memory" => &mut limits.locked_memory,
+                "Max address space" => &mut limits.address_space,
+                "Max file locks" => &mut limits.file_locks,
+                "Max pending signals" => &mut limits.pending_signals,
+                "Max msgqueue size" => &mut limits.msgqueue_size,
+                "Max nice priority" => &mut limits.nice_priority,
+                "Max realtime priority" => &mut limits.realtime_priority,
+                "Max realtime timeout" => &mut limits.realtime_timeout,
+                _ => continue,
+            };
+
+            let segments: Vec<&str> = values.split_whitespace().collect();
+            if segments.len() < 2 {
+                return Err(ProbeError::UnexpectedContent(
+                    "Incorrect number of segments".to_owned(),
+                ));
+            }
+            *field = Limit {
+                soft: parse_limit(segments[0])?,
+                hard: parse_limit(segments[1])?,
+            };
+            fields_encountered += 1;
+        }
+
+        if fields_encountered != PROC_LIMITS_NUMBER_OF_FIELDS {
+            return Err(ProbeError::UnexpectedContent(
+                "Did not encounter all expected fields".to_owned(),
+            ));
+        }
+
+        Ok(limits)
+    }
+
+    fn parse_limit(segment: &str) -> Result<Option<u64>> {
+        match segment {
+            "unlimited" => Ok(None),
+            value => parse_u64(value).map(Some),
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+    use super::super::ProbeError;
+    use super::os::read_and_parse_proc_limits;
+    use super::Limit;
+    use std::path::Path;
+
+    #[test]
+    fn test_read_self() {
+        assert!(super::read_self().is_ok());
+        assert!(super::read_self().unwrap().open_files.soft.is_some());
+    }
+
+    #[test]
+    fn test_read_of() {
+        let pid = unsafe { libc::getpid() };
+        assert_eq!(super::read_of(pid).unwrap(), super::read_self().unwrap());
+    }
+
+    #[test]
+    fn test_read_of_invalid_pid() {
+        assert!(super::read_of(0).is_err());
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_limits() {
+        let path = Path::new("fixtures/linux/process_limits/proc_self_limits");
+        let limits = read_and_parse_proc_limits(path).unwrap();
+
+        assert_eq!(
+            Limit {
+                soft: None,
+                hard: None,
+            },
+            limits.cpu_time
+        );
+        assert_eq!(
+            Limit {
+                soft: Some(8388608),
+                hard: None,
+            },
+            limits.stack_size
+        );
+        assert_eq!(
+            Limit {
+                soft: Some(1024),
+                hard: Some(524288),
+            },
+            limits.open_files
+        );
+        assert_eq!(
+            Limit {
+                soft: Some(0),
+                hard: Some(0),
+            },
+            limits.nice_priority
+        );
+        assert_eq!(
+            Limit {
+                soft: None,
+                hard: None,
+            },
+            limits.realtime_timeout
+        );
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_limits_wrong_path() {
+        match read_and_parse_proc_limits(Path::new("/nonsense")) {
+            Err(ProbeError::IO(_, _)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_limits_incomplete() {
+        let path = Path::new("fixtures/linux/process_limits/proc_self_limits_incomplete");
+        match read_and_parse_proc_limits(path) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_proc_limits_garbage() {
+        let path = Path::new("fixtures/linux/process_limits/proc_self_limits_garbage");
+        match read_and_parse_proc_limits(path) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+}
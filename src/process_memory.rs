@@ -3,6 +3,92 @@
 
 use super::Result;
 
+/// Memory usage of a process. The first fields are parsed from `/proc/<pid>/statm`, the others
+/// from `/proc/<pid>/status`. All values are in KB. Fields not present in the status, like for
+/// kernel threads or on older kernels, are `None`.
+#[derive(Debug, PartialEq)]
+pub struct ProcessMemory {
+    /// Total program size
+    pub size: u64,
+    pub resident: u64,
+    /// Resident memory backed by a file
+    pub shared: u64,
+    pub text: u64,
+    /// Always 0 since Linux 2.6
+    pub lib: u64,
+    /// Data and stack
+    pub data: u64,
+    /// Always 0 since Linux 2.6
+    pub dirty: u64,
+    pub vm_size: Option<u64>,
+    pub vm_rss: Option<u64>,
+    /// Peak resident set size
+    pub vm_hwm: Option<u64>,
+    pub vm_swap: Option<u64>,
+    pub rss_anon: Option<u64>,
+    pub rss_file: Option<u64>,
+    pub rss_shmem: Option<u64>,
+    pub vm_data: Option<u64>,
+    pub vm_stk: Option<u64>,
+}
+
+/// Memory usage of a process that takes sharing with other processes into account, from
+/// `/proc/<pid>/smaps_rollup`. All values are in KB. Fields not reported by the running kernel are
+/// `None`.
+#[derive(Debug, PartialEq)]
+pub struct SmapsRollup {
+    pub rss: u64,
+    /// Proportional set size, shared pages are divided by the number of processes sharing them
+    pub pss: u64,
+    pub pss_anon: Option<u64>,
+    pub pss_file: Option<u64>,
+    pub pss_shmem: Option<u64>,
+    pub shared_clean: u64,
+    pub shared_dirty: u64,
+    pub private_clean: u64,
+    pub private_dirty: u64,
+    pub swap: u64,
+    pub swap_pss: Option<u64>,
+}
+
+impl SmapsRollup {
+    /// Unique set size, the memory that would be freed if the process exits
+    pub fn uss(&self) -> u64 {
+        self.private_clean + self.private_dirty
+    }
+}
+
+/// Get the memory usage of this process
+#[cfg(target_os = "linux")]
+pub fn read_self() -> Result<ProcessMemory> {
+    os::read_self()
+}
+
+/// Get the memory usage of a process with given pid
+#[cfg(target_os = "linux")]
+pub fn read_of(pid: libc::pid_t) -> Result<ProcessMemory> {
+    os::read_of(pid)
+}
+
+/// Get the memory usage of a process with given pid and all its descendants combined.
+/// `vm_hwm` is `None`, since the peaks of the processes are reached at different times.
+#[cfg(target_os = "linux")]
+pub fn read_tree_of(pid: libc::pid_t) -> Result<ProcessMemory> {
+    os::read_tree_of(pid)
+}
+
+/// Get the proportional memory usage of this process
+#[cfg(target_os = "linux")]
+pub fn read_smaps_rollup_self() -> Result<SmapsRollup> {
+    os::read_smaps_rollup_self()
+}
+
+/// Get the proportional memory usage of a process with given pid
+#[cfg(target_os = "linux")]
+pub fn read_smaps_rollup_of(pid: libc::pid_t) -> Result<SmapsRollup> {
+    os::read_smaps_rollup_of(pid)
+}
+
 /// Get the current RSS memory of this process in KB
 #[cfg(target_os = "linux")]
 pub fn current_rss() -> Result<u64> {
@@ -21,14 +107,269 @@ pub fn max_rss() -> u64 {
     os::max_rss()
 }
 
+/// Get the max RSS memory of the largest child of this process that has been waited for in KB
+#[cfg(target_os = "linux")]
+pub fn max_rss_children() -> u64 {
+    os::max_rss_children()
+}
+
 #[cfg(target_os = "linux")]
 mod os {
-    use super::super::file_to_string;
+    use super::super::processes;
     use super::super::ProbeError;
     use super::super::Result;
+    use super::super::{file_to_buf_reader, file_to_string, parse_u64, path_to_string};
+    use super::{ProcessMemory, SmapsRollup};
+    use std::io::BufRead;
     use std::mem;
     use std::path::Path;
 
+    const STATM_NUMBER_OF_FIELDS: usize = 7;
+
+    #[inline]
+    pub fn read_self() -> Result<ProcessMemory> {
+        read_and_parse_process_memory(
+            Path::new("/proc/self/statm"),
+            Path::new("/proc/self/status"),
+        )
+    }
+
+    #[inline]
+    pub fn read_of(pid: libc::pid_t) -> Result<ProcessMemory> {
+        read_and_parse_process_memory(
+            Path::new(&format!("/proc/{}/statm", pid)),
+            Path::new(&format!("/proc/{}/status", pid)),
+        )
+    }
+
+    pub fn read_tree_of(pid: libc::pid_t) -> Result<ProcessMemory> {
+        let descendants = processes::read_descendants_of(pid)?;
+        read_and_parse_tree(Path::new("/proc"), pid, &descendants)
+    }
+
+    pub fn read_and_parse_tree(
+        proc_dir: &Path,
+        pid: libc::pid_t,
+        descendants: &[libc::pid_t],
+    ) -> Result<ProcessMemory> {
+        let read_process = |pid: &libc::pid_t| {
+            let process_dir = proc_dir.join(pid.to_string());
+            read_and_parse_process_memory(&process_dir.join("statm"), &process_dir.join("status"))
+        };
+
+        let mut memory = read_process(&pid)?;
+        for descendant in descendants {
+            match read_process(descendant) {
+                Ok(descendant_memory) => add_process_memory(&mut memory, &descendant_memory),
+                Err(ProbeError::IO(ref e, _)) if processes::os::is_exited(e) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        memory.vm_hwm = None;
+        Ok(memory)
+    }
+
+    // The peak resident set size is left as is, a sum of peaks is not the peak of the sum.
+    pub fn add_process_memory(memory: &mut ProcessMemory, other: &ProcessMemory) {
+        fn add(value: Option<u64>, other: Option<u64>) -> Option<u64> {
+            match (value, other) {
+                (Some(value), Some(other)) => Some(value + other),
+                (value, None) => value,
+                (None, other) => other,
+            }
+        }
+
+        memory.size += other.size;
+        memory.resident += other.resident;
+        memory.shared += other.shared;
+        memory.text += other.text;
+        memory.lib += other.lib;
+        memory.data += other.data;
+        memory.dirty += other.dirty;
+        memory.vm_size = add(memory.vm_size, other.vm_size);
+        memory.vm_rss = add(memory.vm_rss, other.vm_rss);
+        memory.vm_swap = add(memory.vm_swap, other.vm_swap);
+        memory.rss_anon = add(memory.rss_anon, other.rss_anon);
+        memory.rss_file = add(memory.rss_file, other.rss_file);
+        memory.rss_shmem = add(memory.rss_shmem, other.rss_shmem);
+        memory.vm_data = add(memory.vm_data, other.vm_data);
+        memory.vm_stk = add(memory.vm_stk, other.vm_stk);
+    }
+
+    pub fn read_and_parse_process_memory(
+        statm_path: &Path,
+        status_path: &Path,
+    ) -> Result<ProcessMemory> {
+        let raw_data = file_to_string(statm_path)?;
+        let segments: Vec<&str> = raw_data.split_whitespace().collect();
+
+        if segments.len() != STATM_NUMBER_OF_FIELDS {
+            return Err(ProbeError::UnexpectedContent(
+                "Incorrect number of segments".to_owned(),
+            ));
+        }
+
+        // Values are in pages, see `read_and_get_current_rss`.
+        let pagesize = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as u64 / 1024;
+        let mut statm = [0; STATM_NUMBER_OF_FIELDS];
+        for (value, segment) in statm.iter_mut().zip(segments) {
+            *value = parse_u64(segment)? * pagesize;
+        }
+
+        let mut memory = ProcessMemory {
+            size: statm[0],
+            resident: statm[1],
+            shared: statm[2],
+            text: statm[3],
+            lib: statm[4],
+            data: statm[5],
+            dirty: statm[6],
+            vm_size: None,
+            vm_rss: None,
+            vm_hwm: None,
+            vm_swap: None,
+            rss_anon: None,
+            rss_file: None,
+            rss_shmem: None,
+            vm_data: None,
+            vm_stk: None,
+        };
+
+        let reader = file_to_buf_reader(status_path)?;
+        for line_result in reader.lines() {
+            let line = line_result.map_err(|e| ProbeError::IO(e, path_to_string(status_path)))?;
+            let segments: Vec<&str> = line.split_whitespace().collect();
+
+            let field = match segments.first() {
+                Some(&"VmSize:") => &mut memory.vm_size,
+                Some(&"VmRSS:") => &mut memory.vm_rss,
+                Some(&"VmHWM:") => &mut memory.vm_hwm,
+                Some(&"VmSwap:") => &mut memory.vm_swap,
+                Some(&"RssAnon:") => &mut memory.rss_anon,
+                Some(&"RssFile:") => &mut memory.rss_file,
+                Some(&"RssShmem:") => &mut memory.rss_shmem,
+                Some(&"VmData:") => &mut memory.vm_data,
+                Some(&"VmStk:") => &mut memory.vm_stk,
+                _ => continue,
+            };
+            match segments.get(1) {
+                Some(value) => *field = Some(parse_u64(value)?),
+                None => {
+                    return Err(ProbeError::UnexpectedContent(format!(
+                        "Unexpected line '{}'",
+                        line
+                    )))
+                }
+            }
+        }
+
+        Ok(memory)
+    }
+
+    #[inline]
+    pub fn read_smaps_rollup_self() -> Result<SmapsRollup> {
+        read_and_parse_smaps_rollup(
+            Path::new("/proc/self/smaps_rollup"),
+            Path::new("/proc/self/smaps"),
+        )
+    }
+
+    #[inline]
+    pub fn read_smaps_rollup_of(pid: libc::pid_t) -> Result<SmapsRollup> {
+        read_and_parse_smaps_rollup(
+            Path::new(&format!("/proc/{}/smaps_rollup", pid)),
+            Path::new(&format!("/proc/{}/smaps", pid)),
+        )
+    }
+
+    /// `smaps_rollup` is only available since Linux 4.14, on older kernels the values of all
+    /// mappings in `smaps` are summed.
+    pub fn read_and_parse_smaps_rollup(
+        rollup_path: &Path,
+        smaps_path: &Path,
+    ) -> Result<SmapsRollup> {
+        let path = if rollup_path.exists() {
+            rollup_path
+        } else {
+            smaps_path
+        };
+        let reader = file_to_buf_reader(path)?;
+
+        let mut rss = None;
+        let mut pss = None;
+        let mut rollup = SmapsRollup {
+            rss: 0,
+            pss: 0,
+            pss_anon: None,
+            pss_file: None,
+            pss_shmem: None,
+            shared_clean: 0,
+            shared_dirty: 0,
+            private_clean: 0,
+            private_dirty: 0,
+            swap: 0,
+            swap_pss: None,
+        };
+
+        for line_result in reader.lines() {
+            let line = line_result.map_err(|e| ProbeError::IO(e, path_to_string(path)))?;
+            let segments: Vec<&str> = line.split_whitespace().collect();
+
+            // Mapping header lines and fields we don't use are skipped.
+            let field = match segments.first() {
+                Some(&"Rss:") => &mut rss,
+                Some(&"Pss:") => &mut pss,
+                Some(&"Pss_Anon:") => &mut rollup.pss_anon,
+                Some(&"Pss_File:") => &mut rollup.pss_file,
+                Some(&"Pss_Shmem:") => &mut rollup.pss_shmem,
+                Some(&"SwapPss:") => &mut rollup.swap_pss,
+                Some(&"Shared_Clean:") => {
+                    rollup.shared_clean += parse_smaps_value(&segments)?;
+                    continue;
+                }
+                Some(&"Shared_Dirty:") => {
+                    rollup.shared_dirty += parse_smaps_value(&segments)?;
+                    continue;
+                }
+                Some(&"Private_Clean:") => {
+                    rollup.private_clean += parse_smaps_value(&segments)?;
+                    continue;
+                }
+                Some(&"Private_Dirty:") => {
+                    rollup.private_dirty += parse_smaps_value(&segments)?;
+                    continue;
+                }
+                Some(&"Swap:") => {
+                    rollup.swap += parse_smaps_value(&segments)?;
+                    continue;
+                }
+                _ => continue,
+            };
+            *field = Some(field.unwrap_or(0) + parse_smaps_value(&segments)?);
+        }
+
+        match (rss, pss) {
+            (Some(rss), Some(pss)) => {
+                rollup.rss = rss;
+                rollup.pss = pss;
+                Ok(rollup)
+            }
+            _ => Err(ProbeError::UnexpectedContent(
+                "Did not encounter all expected fields".to_owned(),
+            )),
+        }
+    }
+
+    // columns: Field: value kB
+    fn parse_smaps_value(segments: &[&str]) -> Result<u64> {
+        match segments.get(1) {
+            Some(value) => parse_u64(value),
+            None => Err(ProbeError::UnexpectedContent(
+                "Incorrect number of segments".to_owned(),
+            )),
+        }
+    }
+
     #[inline]
     pub fn current_rss() -> Result<u64> {
         read_and_get_current_rss(&Path::new("/proc/self/statm"))
@@ -70,12 +411,23 @@ mod os {
         }
         .ru_maxrss as u64
     }
+
+    #[inline]
+    pub fn max_rss_children() -> u64 {
+        let mut rusage = mem::MaybeUninit::<libc::rusage>::uninit();
+        unsafe {
+            libc::getrusage(libc::RUSAGE_CHILDREN, rusage.as_mut_ptr());
+            rusage.assume_init()
+        }
+        .ru_maxrss as u64
+    }
 }
 
 #[cfg(test)]
 #[cfg(target_os = "linux")]
 mod tests {
     use super::super::ProbeError;
+    use super::{ProcessMemory, SmapsRollup};
     use std::path::Path;
 
     #[test]
@@ -134,6 +486,260 @@ mod tests {
         assert!(super::current_rss_of(0).is_err());
     }
 
+    #[test]
+    fn test_read_self() {
+        let memory = super::read_self().unwrap();
+        assert!(memory.resident > 1_000);
+        assert!(memory.size >= memory.resident);
+        assert!(memory.vm_rss.is_some());
+    }
+
+    #[test]
+    fn test_read_of() {
+        let pid = unsafe { libc::getpid() };
+        assert!(super::read_of(pid).is_ok());
+    }
+
+    #[test]
+    fn test_read_of_invalid_pid() {
+        assert!(super::read_of(0).is_err());
+    }
+
+    #[test]
+    fn test_read_and_parse_process_memory() {
+        let memory = super::os::read_and_parse_process_memory(
+            Path::new("fixtures/linux/process_memory/proc_self_statm"),
+            Path::new("fixtures/linux/process_memory/proc_self_status"),
+        )
+        .unwrap();
+
+        let expected = ProcessMemory {
+            size: 25492,
+            resident: 4552,
+            shared: 1712,
+            text: 856,
+            lib: 0,
+            data: 3020,
+            dirty: 0,
+            vm_size: Some(25492),
+            vm_rss: Some(4552),
+            vm_hwm: Some(5120),
+            vm_swap: Some(256),
+            rss_anon: Some(2840),
+            rss_file: Some(1712),
+            rss_shmem: Some(0),
+            vm_data: Some(3020),
+            vm_stk: Some(132),
+        };
+        assert_eq!(expected, memory);
+    }
+
+    #[test]
+    fn test_read_and_parse_process_memory_kernel_thread() {
+        let memory = super::os::read_and_parse_process_memory(
+            Path::new("fixtures/linux/process_memory/proc_self_statm"),
+            Path::new("fixtures/linux/process_memory/proc_self_status_kernel_thread"),
+        )
+        .unwrap();
+
+        assert_eq!(None, memory.vm_size);
+        assert_eq!(None, memory.vm_rss);
+        assert_eq!(None, memory.rss_anon);
+    }
+
+    #[test]
+    fn test_read_and_parse_process_memory_wrong_path() {
+        match super::os::read_and_parse_process_memory(
+            Path::new("/nonsense"),
+            Path::new("fixtures/linux/process_memory/proc_self_status"),
+        ) {
+            Err(ProbeError::IO(_, _)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_process_memory_incomplete() {
+        match super::os::read_and_parse_process_memory(
+            Path::new("fixtures/linux/process_memory/proc_self_statm_incomplete"),
+            Path::new("fixtures/linux/process_memory/proc_self_status"),
+        ) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_process_memory_garbage() {
+        match super::os::read_and_parse_process_memory(
+            Path::new("fixtures/linux/process_memory/proc_self_statm"),
+            Path::new("fixtures/linux/process_memory/proc_self_status_garbage"),
+        ) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_smaps_rollup_self() {
+        let rollup = super::read_smaps_rollup_self().unwrap();
+        assert!(rollup.rss > 1_000);
+        assert!(rollup.pss <= rollup.rss);
+        assert!(rollup.uss() <= rollup.pss);
+    }
+
+    #[test]
+    fn test_read_smaps_rollup_of_invalid_pid() {
+        assert!(super::read_smaps_rollup_of(0).is_err());
+    }
+
+    #[test]
+    fn test_read_and_parse_smaps_rollup() {
+        let rollup = super::os::read_and_parse_smaps_rollup(
+            Path::new("fixtures/linux/process_memory/proc_self_smaps_rollup"),
+            Path::new("/nonsense"),
+        )
+        .unwrap();
+
+        let expected = SmapsRollup {
+            rss: 4552,
+            pss: 3318,
+            pss_anon: Some(2840),
+            pss_file: Some(478),
+            pss_shmem: Some(0),
+            shared_clean: 1520,
+            shared_dirty: 0,
+            private_clean: 192,
+            private_dirty: 2840,
+            swap: 256,
+            swap_pss: Some(256),
+        };
+        assert_eq!(expected, rollup);
+        assert_eq!(3032, rollup.uss());
+    }
+
+    #[test]
+    fn test_read_and_parse_smaps_rollup_fallback() {
+        let rollup = super::os::read_and_parse_smaps_rollup(
+            Path::new("/nonsense"),
+            Path::new("fixtures/linux/process_memory/proc_self_smaps"),
+        )
+        .unwrap();
+
+        let expected = SmapsRollup {
+            rss: 1128,
+            pss: 582,
+            pss_anon: None,
+            pss_file: None,
+            pss_shmem: None,
+            shared_clean: 1084,
+            shared_dirty: 0,
+            private_clean: 8,
+            private_dirty: 36,
+            swap: 12,
+            swap_pss: Some(12),
+        };
+        assert_eq!(expected, rollup);
+    }
+
+    #[test]
+    fn test_read_and_parse_smaps_rollup_wrong_path() {
+        match super::os::read_and_parse_smaps_rollup(Path::new("/nonsense"), Path::new("/nonsense"))
+        {
+            Err(ProbeError::IO(_, _)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_smaps_rollup_incomplete() {
+        match super::os::read_and_parse_smaps_rollup(
+            Path::new("fixtures/linux/process_memory/proc_self_smaps_rollup_incomplete"),
+            Path::new("/nonsense"),
+        ) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_smaps_rollup_garbage() {
+        match super::os::read_and_parse_smaps_rollup(
+            Path::new("fixtures/linux/process_memory/proc_self_smaps_rollup_garbage"),
+            Path::new("/nonsense"),
+        ) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_tree_of() {
+        // Other tests spawn children, the tree is covered by `test_read_and_parse_tree`
+        let pid = unsafe { libc::getpid() };
+        assert!(super::read_tree_of(pid).is_ok());
+    }
+
+    #[test]
+    fn test_read_tree_of_invalid_pid() {
+        assert!(super::read_tree_of(0).is_err());
+    }
+
+    #[test]
+    fn test_read_and_parse_tree() {
+        let path = Path::new("fixtures/linux/process_tree/proc_1");
+        let memory = super::os::read_and_parse_tree(path, 2741, &[2750, 2751, 2752]).unwrap();
+
+        // Pid 2752 exited while reading and is skipped
+        assert_eq!(24552, memory.resident);
+        assert_eq!(Some(24552), memory.vm_rss);
+        assert_eq!(Some(18440), memory.rss_anon);
+        assert_eq!(Some(256), memory.vm_swap);
+        assert_eq!(None, memory.vm_hwm);
+    }
+
+    #[test]
+    fn test_read_and_parse_tree_wrong_path() {
+        match super::os::read_and_parse_tree(Path::new("/nonsense"), 2741, &[]) {
+            Err(ProbeError::IO(_, _)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_add_process_memory() {
+        let path = Path::new("fixtures/linux/process_memory/proc_self_statm");
+        let mut memory = super::os::read_and_parse_process_memory(
+            path,
+            Path::new("fixtures/linux/process_memory/proc_self_status"),
+        )
+        .unwrap();
+        let kernel_thread_memory = super::os::read_and_parse_process_memory(
+            path,
+            Path::new("fixtures/linux/process_memory/proc_self_status_kernel_thread"),
+        )
+        .unwrap();
+        let other_memory = super::os::read_and_parse_process_memory(
+            path,
+            Path::new("fixtures/linux/process_memory/proc_self_status"),
+        )
+        .unwrap();
+
+        super::os::add_process_memory(&mut memory, &kernel_thread_memory);
+        super::os::add_process_memory(&mut memory, &other_memory);
+
+        assert_eq!(13656, memory.resident);
+        assert_eq!(Some(9104), memory.vm_rss);
+        assert_eq!(Some(512), memory.vm_swap);
+        assert_eq!(Some(5680), memory.rss_anon);
+    }
+
+    #[test]
+    fn test_max_rss_children() {
+        // The test process may not have waited for any children
+        assert!(super::max_rss_children() < 10_000_000);
+    }
+
     #[test]
     fn test_max_rss() {
         // See if it's a sort of sane value, between 1 and 700 mb
@@ -0,0 +1,202 @@
+use super::processes::ProcessState;
+use super::Result;
+
+/// Status and lifecycle details of a process from `/proc/<pid>`
+#[derive(Debug, PartialEq)]
+pub struct ProcessStatus {
+    pub pid: libc::pid_t,
+    pub state: ProcessState,
+    /// Start time in seconds since the Unix epoch
+    pub start_time: u64,
+    /// Time since the process started in seconds
+    pub uptime: u64,
+    pub nice: i64,
+    pub priority: i64,
+    pub threads: u64,
+    pub voluntary_ctxt_switches: u64,
+    pub nonvoluntary_ctxt_switches: u64,
+    /// Badness score used by the OOM killer to select a process
+    pub oom_score: u64,
+    /// Adjustment of the OOM score, from -1000 to 1000
+    pub oom_score_adj: i64,
+}
+
+/// Read the status of this process.
+#[cfg(target_os = "linux")]
+pub fn read_self() -> Result<ProcessStatus> {
+    os::read_of(unsafe { libc::getpid() })
+}
+
+/// Read the status of a process with given pid.
+#[cfg(target_os = "linux")]
+pub fn read_of(pid: libc::pid_t) -> Result<ProcessStatus> {
+    os::read_of(pid)
+}
+
+#[cfg(target_os = "linux")]
+mod os {
+    use super::super::processes::os::{
+        parse_state, read_and_parse_ctxt_switches, split_proc_pid_stat,
+    };
+    use super::super::{
+        cpu, file_to_string, parse_i64, parse_u64, read_file_value_as_u64, ProbeError, Result,
+    };
+    use super::ProcessStatus;
+    use std::path::Path;
+
+    #[inline]
+    pub fn read_of(pid: libc::pid_t) -> Result<ProcessStatus> {
+        let boot_time = cpu::proc::read_boot_time()?;
+        read_and_parse_process_status(
+            Path::new(&format!("/proc/{}", pid)),
+            pid,
+            Path::new("/proc/uptime"),
+            boot_time,
+        )
+    }
+
+    pub fn read_and_parse_process_status(
+        process_dir: &Path,
+        pid: libc::pid_t,
+        uptime_path: &Path,
+        boot_time: u64,
+    ) -> Result<ProcessStatus> {
+        let stat = file_to_string(&process_dir.join("stat"))?;
+        let (_, fields) = split_proc_pid_stat(&stat)?;
+        if fields.len() < 20 {
+            return Err(ProbeError::UnexpectedContent(
+                "Incorrect number of segments".to_owned(),
+            ));
+        }
+
+        let ticks_per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) } as u64;
+        let started_after_boot = parse_u64(fields[19])? / ticks_per_second;
+        let (voluntary_ctxt_switches, nonvoluntary_ctxt_switches) =
+            read_and_parse_ctxt_switches(&process_dir.join("status"))?;
+        let oom_score_adj = file_to_string(&process_dir.join("oom_score_adj"))?;
+
+        Ok(ProcessStatus {
+            pid,
+            state: parse_state(fields[0])?,
+            start_time: boot_time + started_after_boot,
+            uptime: read_and_parse_uptime(uptime_path)?.saturating_sub(started_after_boot),
+            nice: parse_i64(fields[16])?,
+            priority: parse_i64(fields[15])?,
+            threads: parse_u64(fields[17])?,
+            voluntary_ctxt_switches,
+            nonvoluntary_ctxt_switches,
+            oom_score: read_file_value_as_u64(&process_dir.join("oom_score"))?,
+            oom_score_adj: parse_i64(oom_score_adj.trim())?,
+        })
+    }
+
+    // columns: uptime idle, both in seconds with fractions
+    fn read_and_parse_uptime(path: &Path) -> Result<u64> {
+        let raw_data = file_to_string(path)?;
+        match raw_data
+            .split_whitespace()
+            .next()
+            .map(|uptime| uptime.parse::<f64>())
+        {
+            Some(Ok(uptime)) => Ok(uptime as u64),
+            _ => Err(ProbeError::UnexpectedContent(format!(
+                "Could not parse uptime from '{}'",
+                raw_data.trim()
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+    use super::super::processes::ProcessState;
+    use super::super::ProbeError;
+    use super::os::read_and_parse_process_status;
+    use super::ProcessStatus;
+    use std::path::Path;
+
+    #[test]
+    fn test_read_self() {
+        let status = super::read_self().unwrap();
+        assert_eq!(unsafe { libc::getpid() }, status.pid);
+        assert!(status.threads >= 1);
+        assert!(status.start_time > 1_000_000_000);
+    }
+
+    #[test]
+    fn test_read_of() {
+        let pid = unsafe { libc::getpid() };
+        assert!(super::read_of(pid).is_ok());
+    }
+
+    #[test]
+    fn test_read_of_invalid_pid() {
+        assert!(super::read_of(0).is_err());
+    }
+
+    #[test]
+    fn test_read_and_parse_process_status() {
+        let status = read_and_parse_process_status(
+            Path::new("fixtures/linux/process_status/proc/2741"),
+            2741,
+            Path::new("fixtures/linux/process_status/proc/uptime"),
+            1_700_000_000,
+        )
+        .unwrap();
+
+        let expected = ProcessStatus {
+            pid: 2741,
+            state: ProcessState::DiskSleep,
+            start_time: 1_700_000_812,
+            uptime: 11_533,
+            nice: -5,
+            priority: 15,
+            threads: 2,
+            voluntary_ctxt_switches: 1290,
+            nonvoluntary_ctxt_switches: 37,
+            oom_score: 668,
+            oom_score_adj: -100,
+        };
+        assert_eq!(expected, status);
+    }
+
+    #[test]
+    fn test_read_and_parse_process_status_wrong_path() {
+        match read_and_parse_process_status(
+            Path::new("/nonsense"),
+            2741,
+            Path::new("fixtures/linux/process_status/proc/uptime"),
+            0,
+        ) {
+            Err(ProbeError::IO(_, _)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_process_status_incomplete() {
+        match read_and_parse_process_status(
+            Path::new("fixtures/linux/process_status/proc_incomplete/2741"),
+            2741,
+            Path::new("fixtures/linux/process_status/proc/uptime"),
+            0,
+        ) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_process_status_garbage() {
+        match read_and_parse_process_status(
+            Path::new("fixtures/linux/process_status/proc_garbage/2741"),
+            2741,
+            Path::new("fixtures/linux/process_status/proc/uptime"),
+            0,
+        ) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+}
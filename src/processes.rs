@@ -0,0 +1,573 @@
+use super::{calculate_time_difference, time_adjusted, Result};
+use std::collections::{HashMap, HashSet};
+
+/// State of a process as reported in `/proc/<pid>/stat`
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ProcessState {
+    Running,
+    Sleeping,
+    /// Uninterruptible sleep, usually waiting for I/O
+    DiskSleep,
+    Zombie,
+    Stopped,
+    TracingStop,
+    Dead,
+    Idle,
+    /// State not known to this crate, with the character reported by the kernel
+    Other(char),
+}
+
+impl ProcessState {
+    fn from_char(state: char) -> ProcessState {
+        match state {
+            'R' => ProcessState::Running,
+            'S' => ProcessState::Sleeping,
+            'D' => ProcessState::DiskSleep,
+            'Z' => ProcessState::Zombie,
+            'T' => ProcessState::Stopped,
+            't' => ProcessState::TracingStop,
+            'X' | 'x' => ProcessState::Dead,
+            'I' => ProcessState::Idle,
+            other => ProcessState::Other(other),
+        }
+    }
+}
+
+/// A process in the process table
+#[derive(Debug, PartialEq)]
+pub struct Process {
+    pub pid: libc::pid_t,
+    pub ppid: libc::pid_t,
+    pub name: String,
+    /// Empty for kernel threads and zombie processes
+    pub cmdline: Vec<String>,
+    pub state: ProcessState,
+    /// Real user id
+    pub uid: libc::uid_t,
+    pub threads: u64,
+    /// Resident set size in KB
+    pub rss: u64,
+    /// Start time in seconds since the Unix epoch
+    pub start_time: u64,
+    /// Time spent in user and kernel mode in nanoseconds
+    pub cpu_time: u64,
+}
+
+pub type Processes = HashMap<libc::pid_t, Process>;
+
+/// Snapshot of all processes at a certain time
+#[derive(Debug, PartialEq)]
+pub struct ProcessTable {
+    pub precise_time_ns: u64,
+    pub processes: Processes,
+    /// Pids of processes that could not be read, such as processes of other users when `/proc`
+    /// is mounted with `hidepid` or processes with unexpected content while they exec
+    pub unreadable: Vec<libc::pid_t>,
+}
+
+impl ProcessTable {
+    /// The `n` processes with the highest resident set size, highest first.
+    pub fn top_by_rss(&self, n: usize) -> Vec<&Process> {
+        let mut processes: Vec<&Process> = self.processes.values().collect();
+        processes.sort_by(|a, b| b.rss.cmp(&a.rss).then(a.pid.cmp(&b.pid)));
+        processes.truncate(n);
+        processes
+    }
+
+    /// Pids of all children of a process, and their children, recursively.
+    pub fn descendants_of(&self, pid: libc::pid_t) -> Vec<libc::pid_t> {
+        let mut children: HashMap<libc::pid_t, Vec<libc::pid_t>> = HashMap::new();
+        for process in self.processes.values().filter(|p| p.ppid != p.pid) {
+            children.entry(process.ppid).or_default().push(process.pid);
+        }
+
+        // A pid that is reused while `/proc` is read can make the ppid links form a cycle.
+        let mut visited = HashSet::new();
+        visited.insert(pid);
+        let mut descendants = Vec::new();
+        let mut parents = vec![pid];
+        while let Some(parent) = parents.pop() {
+            for child in children.get(&parent).into_iter().flatten() {
+                if visited.insert(*child) {
+                    descendants.push(*child);
+                    parents.push(*child);
+                }
+            }
+        }
+        descendants.sort_unstable();
+        descendants
+    }
+
+    /// The `n` processes that used the most CPU time between this snapshot and a snapshot in the
+    /// future, highest first. Processes that are not present in both snapshots are skipped.
+    pub fn top_by_cpu(&self, next_table: &ProcessTable, n: usize) -> Result<Vec<ProcessCpuUsage>> {
+        let time_difference =
+            calculate_time_difference(self.precise_time_ns, next_table.precise_time_ns)?;
+
+        let mut usages = Vec::new();
+        for (pid, process) in self.processes.iter() {
+            let next_process = match next_table.processes.get(pid) {
+                // The pid was reused by another process when the start time differs
+                Some(next_process) if next_process.start_time == process.start_time => next_process,
+                _ => continue,
+            };
+
+            usages.push(ProcessCpuUsage {
+                pid: *pid,
+                name: next_process.name.to_owned(),
+                cpu_time: time_adjusted(
+                    "cpu_time",
+                    next_process.cpu_time,
+                    process.cpu_time,
+                    time_difference,
+                )?,
+            });
+        }
+
+        usages.sort_by(|a, b| b.cpu_time.cmp(&a.cpu_time).then(a.pid.cmp(&b.pid)));
+        usages.truncate(n);
+        Ok(usages)
+    }
+}
+
+/// CPU time used by a process for a minute
+#[derive(Debug, PartialEq)]
+pub struct ProcessCpuUsage {
+    pub pid: libc::pid_t,
+    pub name: String,
+    /// Time spent in user and kernel mode in nanoseconds
+    pub cpu_time: u64,
+}
+
+impl ProcessCpuUsage {
+    /// CPU time in percentages of a single CPU
+    pub fn in_percentage(&self) -> f32 {
+        // 60_000_000_000 being the total value. This is 60 seconds expressed in nanoseconds.
+        (self.cpu_time as f32 / 60_000_000_000.0) * 100.0
+    }
+}
+
+/// Read all processes in `/proc`. Processes that exit while reading are skipped, processes that
+/// can't be read are listed in `unreadable`.
+#[cfg(target_os = "linux")]
+pub fn read() -> Result<ProcessTable> {
+    os::read()
+}
+
+/// Read the pids of all descendants of a process with given pid.
+#[cfg(target_os = "linux")]
+pub fn read_descendants_of(pid: libc::pid_t) -> Result<Vec<libc::pid_t>> {
+    os::read().map(|table| table.descendants_of(pid))
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) mod os {
+    use super::super::{
+        cpu, file_to_buf_reader, file_to_string, parse_u64, path_to_string, precise_time_ns,
+        ticks_to_ns, ProbeError, Result,
+    };
+    use super::{Process, ProcessState, ProcessTable};
+    use std::collections::HashMap;
+    use std::fs;
+    use std::io::{self, BufRead};
+    use std::path::Path;
+
+    const PROC_STATUS_CTXT_SWITCHES_NUMBER_OF_FIELDS: usize = 2;
+
+    #[inline]
+    pub fn read() -> Result<ProcessTable> {
+        let boot_time = cpu::proc::read_boot_time()?;
+        read_and_parse_processes(Path::new("/proc"), boot_time)
+    }
+
+    pub fn read_and_parse_processes(proc_dir: &Path, boot_time: u64) -> Result<ProcessTable> {
+        let precise_time_ns = precise_time_ns();
+        let entries =
+            fs::read_dir(proc_dir).map_err(|e| ProbeError::IO(e, path_to_string(proc_dir)))?;
+
+        let mut processes = HashMap::new();
+        let mut unreadable = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|e| ProbeError::IO(e, path_to_string(proc_dir)))?;
+            let pid: libc::pid_t = match entry.file_name().to_str().and_then(|n| n.parse().ok()) {
+                Some(pid) => pid,
+                None => continue,
+            };
+
+            match read_and_parse_process(&entry.path(), pid, boot_time) {
+                Ok(process) => {
+                    processes.insert(pid, process);
+                }
+                Err(ProbeError::IO(ref e, _)) if is_exited(e) => continue,
+                Err(_) => unreadable.push(pid),
+            }
+        }
+        unreadable.sort_unstable();
+
+        Ok(ProcessTable {
+            precise_time_ns,
+            processes,
+            unreadable,
+        })
+    }
+
+    /// Files of a process disappear when it exits, reading them fails when it exits while reading.
+    pub fn is_exited(error: &io::Error) -> bool {
+        error.kind() == io::ErrorKind::NotFound || error.raw_os_error() == Some(libc::ESRCH)
+    }
+
+    pub fn read_and_parse_process(
+        process_dir: &Path,
+        pid: libc::pid_t,
+        boot_time: u64,
+    ) -> Result<Process> {
+        let stat = file_to_string(&process_dir.join("stat"))?;
+        let (name, fields) = split_proc_pid_stat(&stat)?;
+        if fields.len() < 22 {
+            return Err(ProbeError::UnexpectedContent(
+                "Incorrect number of segments".to_owned(),
+            ));
+        }
+
+        let ticks_per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) } as u64;
+        let pagesize = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as u64 / 1024;
+
+        Ok(Process {
+            pid,
+            ppid: parse_pid(fields[1])?,
+            name: name.to_owned(),
+            cmdline: read_and_parse_cmdline(&process_dir.join("cmdline"))?,
+            state: parse_state(fields[0])?,
+            uid: read_and_parse_uid(&process_dir.join("status"))?,
+            threads: parse_u64(fields[17])?,
+            rss: parse_u64(fields[21])? * pagesize,
+            start_time: boot_time + parse_u64(fields[19])? / ticks_per_second,
+            cpu_time: ticks_to_ns(parse_u64(fields[11])? + parse_u64(fields[12])?),
+        })
+    }
+
+    /// Split the contents of `/proc/<pid>/stat` in the command name and the fields after it. The
+    /// command name can contain spaces and parentheses, the fields start after the last closing
+    /// parenthesis with the state, which is field 3.
+    pub fn split_proc_pid_stat(raw_data: &str) -> Result<(&str, Vec<&str>)> {
+        match (raw_data.find('('), raw_data.rfind(')')) {
+            (Some(start), Some(end)) if start < end => Ok((
+                &raw_data[start + 1..end],
+                raw_data[end + 1..].split_whitespace().collect(),
+            )),
+            _ => Err(ProbeError::UnexpectedContent(
+                "Could not find the command name".to_owned(),
+            )),
+        }
+    }
+
+    pub fn parse_state(segment: &str) -> Result<ProcessState> {
+        let mut chars = segment.chars();
+        match (chars.next(), chars.next()) {
+            (Some(state), None) => Ok(ProcessState::from_char(state)),
+            _ => Err(ProbeError::UnexpectedContent(format!(
+                "Could not parse '{}' as process state",
+                segment
+            ))),
+        }
+    }
+
+    pub fn parse_pid(segment: &str) -> Result<libc::pid_t> {
+        segment.parse().map_err(|_| {
+            ProbeError::UnexpectedContent(format!("Could not parse '{}' as pid", segment))
+        })
+    }
+
+    // Arguments are separated by null bytes.
+    fn read_and_parse_cmdline(path: &Path) -> Result<Vec<String>> {
+        let raw_data = fs::read(path).map_err(|e| ProbeError::IO(e, path_to_string(path)))?;
+        Ok(raw_data
+            .split(|byte| *byte == 0)
+            .filter(|argument| !argument.is_empty())
+            .map(|argument| String::from_utf8_lossy(argument).into_owned())
+            .collect())
+    }
+
+    // Voluntary and nonvoluntary context switches from `/proc/<pid>/status`.
+    pub fn read_and_parse_ctxt_switches(path: &Path) -> Result<(u64, u64)> {
+        let reader = file_to_buf_reader(path)?;
+
+        let mut ctxt_switches = (0, 0);
+        let mut fields_encountered = 0;
+        for line_result in reader.lines() {
+            let line = line_result.map_err(|e| ProbeError::IO(e, path_to_string(path)))?;
+            let segments: Vec<&str> = line.split_whitespace().collect();
+
+            let field = match segments.first() {
+                Some(&"voluntary_ctxt_switches:") => &mut ctxt_switches.0,
+                Some(&"nonvoluntary_ctxt_switches:") => &mut ctxt_switches.1,
+                _ => continue,
+            };
+            *field = match segments.get(1) {
+                Some(value) => parse_u64(value)?,
+                None => {
+                    return Err(ProbeError::UnexpectedContent(format!(
+                        "Unexpected line '{}'",
+                        line
+                    )))
+                }
+            };
+            fields_encountered += 1;
+        }
+
+        if fields_encountered != PROC_STATUS_CTXT_SWITCHES_NUMBER_OF_FIELDS {
+            return Err(ProbeError::UnexpectedContent(
+                "Did not encounter all expected fields".to_owned(),
+            ));
+        }
+
+        Ok(ctxt_switches)
+    }
+
+    // columns: Uid: real effective saved filesystem
+    fn read_and_parse_uid(path: &Path) -> Result<libc::uid_t> {
+        let reader = file_to_buf_reader(path)?;
+        for line_result in reader.lines() {
+            let line = line_result.map_err(|e| ProbeError::IO(e, path_to_string(path)))?;
+            if let Some(uids) = line.strip_prefix("Uid:") {
+                return match uids.split_whitespace().next().map(|uid| uid.parse()) {
+                    Some(Ok(uid)) => Ok(uid),
+                    _ => Err(ProbeError::UnexpectedContent(format!(
+                        "Could not parse uid from '{}'",
+                        line
+                    ))),
+                };
+            }
+        }
+
+        Err(ProbeError::UnexpectedContent(
+            "Did not encounter the Uid field".to_owned(),
+        ))
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+    use super::super::ProbeError;
+    use super::os::{read_and_parse_processes, split_proc_pid_stat};
+    use super::{Process, ProcessState, ProcessTable};
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    #[test]
+    fn test_read() {
+        let table = super::read().unwrap();
+        let pid = unsafe { libc::getpid() };
+
+        let process = table.processes.get(&pid).unwrap();
+        assert!(process.rss > 1_000);
+        assert!(process.threads >= 1);
+        assert!(!process.cmdline.is_empty());
+    }
+
+    #[test]
+    fn test_read_and_parse_processes() {
+        let path = Path::new("fixtures/linux/processes/proc");
+        let table = read_and_parse_processes(path, 1_700_000_000).unwrap();
+
+        // The process without files exited while reading and is skipped
+        assert_eq!(3, table.processes.len());
+        // The processes with a stat file that can't be read or parsed are listed as unreadable
+        assert_eq!(vec![5, 1000], table.unreadable);
+        assert_eq!(
+            &Process {
+                pid: 2741,
+                ppid: 1,
+                name: "ruby: worker (2)".to_owned(),
+                cmdline: vec![
+                    "ruby".to_owned(),
+                    "worker.rb".to_owned(),
+                    "--queue=default".to_owned()
+                ],
+                state: ProcessState::Sleeping,
+                uid: 1000,
+                threads: 2,
+                rss: 4552,
+                start_time: 1_700_000_812,
+                cpu_time: 17_600_000_000,
+            },
+            table.processes.get(&2741).unwrap()
+        );
+
+        let kthread = table.processes.get(&18).unwrap();
+        assert_eq!("kworker/0:1", kthread.name);
+        assert_eq!(ProcessState::Idle, kthread.state);
+        assert!(kthread.cmdline.is_empty());
+        assert_eq!(0, kthread.rss);
+    }
+
+    #[test]
+    fn test_read_and_parse_processes_wrong_path() {
+        match read_and_parse_processes(Path::new("/nonsense"), 0) {
+            Err(ProbeError::IO(_, _)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_processes_garbage() {
+        // A single process with unexpected content does not hide the other processes
+        let path = Path::new("fixtures/linux/processes/proc");
+        let table = read_and_parse_processes(path, 0).unwrap();
+
+        assert!(table.unreadable.contains(&5));
+        assert!(!table.processes.contains_key(&5));
+        assert_eq!(3, table.processes.len());
+    }
+
+    #[test]
+    fn test_split_proc_pid_stat() {
+        let (name, fields) = split_proc_pid_stat("12 (a (b) c) S 1 12").unwrap();
+        assert_eq!("a (b) c", name);
+        assert_eq!(vec!["S", "1", "12"], fields);
+
+        assert!(split_proc_pid_stat("12 S 1 12").is_err());
+    }
+
+    #[test]
+    fn test_read_descendants_of() {
+        assert!(!super::read_descendants_of(1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_descendants_of() {
+        let mut processes = helpers::processes(&[(1, 0, 0), (10, 0, 0), (11, 0, 0), (12, 0, 0)]);
+        processes.insert(20, helpers::child_process(20, 10));
+        processes.insert(21, helpers::child_process(21, 10));
+        processes.insert(30, helpers::child_process(30, 21));
+        let table = ProcessTable {
+            precise_time_ns: 60_000_000_000,
+            processes,
+            unreadable: vec![],
+        };
+
+        assert_eq!(vec![20, 21, 30], table.descendants_of(10));
+        assert_eq!(vec![30], table.descendants_of(21));
+        assert!(table.descendants_of(30).is_empty());
+        assert!(table.descendants_of(99).is_empty());
+    }
+
+    #[test]
+    fn test_descendants_of_cycle() {
+        let mut processes = HashMap::new();
+        processes.insert(40, helpers::child_process(40, 42));
+        processes.insert(41, helpers::child_process(41, 40));
+        processes.insert(42, helpers::child_process(42, 41));
+        let table = ProcessTable {
+            precise_time_ns: 60_000_000_000,
+            processes,
+            unreadable: vec![],
+        };
+
+        assert_eq!(vec![41, 42], table.descendants_of(40));
+    }
+
+    #[test]
+    fn test_top_by_rss() {
+        let table = ProcessTable {
+            precise_time_ns: 60_000_000_000,
+            processes: helpers::processes(&[(1, 100, 0), (2, 300, 0), (3, 200, 0)]),
+            unreadable: vec![],
+        };
+
+        let top: Vec<libc::pid_t> = table.top_by_rss(2).iter().map(|p| p.pid).collect();
+        assert_eq!(vec![2, 3], top);
+        assert_eq!(3, table.top_by_rss(10).len());
+    }
+
+    #[test]
+    fn test_top_by_cpu() {
+        let table1 = ProcessTable {
+            precise_time_ns: 60_000_000_000,
+            processes: helpers::processes(&[
+                (1, 0, 1_000_000_000),
+                (2, 0, 1_000_000_000),
+                (3, 0, 1_000_000_000),
+            ]),
+            unreadable: vec![],
+        };
+        let mut processes = helpers::processes(&[
+            (1, 0, 1_500_000_000),
+            (2, 0, 7_000_000_000),
+            (4, 0, 9_000_000_000),
+        ]);
+        // Pid 3 is reused by a new process
+        let mut reused = helpers::process(3, 0, 0);
+        reused.start_time = 1_700_000_900;
+        processes.insert(3, reused);
+        let table2 = ProcessTable {
+            precise_time_ns: 90_000_000_000,
+            processes,
+            unreadable: vec![],
+        };
+
+        let top = table1.top_by_cpu(&table2, 10).unwrap();
+        assert_eq!(2, top.len());
+        assert_eq!(2, top[0].pid);
+        assert_eq!(12_000_000_000, top[0].cpu_time);
+        assert!(top[0].in_percentage() > 19.9);
+        assert!(top[0].in_percentage() <= 20.0);
+        assert_eq!(1, top[1].pid);
+        assert_eq!(1_000_000_000, top[1].cpu_time);
+
+        assert_eq!(1, table1.top_by_cpu(&table2, 1).unwrap().len());
+    }
+
+    #[test]
+    fn test_top_by_cpu_wrong_times() {
+        let table1 = ProcessTable {
+            precise_time_ns: 90_000_000_000,
+            processes: HashMap::new(),
+            unreadable: vec![],
+        };
+        let table2 = ProcessTable {
+            precise_time_ns: 60_000_000_000,
+            processes: HashMap::new(),
+            unreadable: vec![],
+        };
+
+        match table1.top_by_cpu(&table2, 10) {
+            Err(ProbeError::InvalidInput(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    mod helpers {
+        use super::super::{Process, ProcessState, Processes};
+
+        pub fn process(pid: libc::pid_t, rss: u64, cpu_time: u64) -> Process {
+            Process {
+                pid,
+                ppid: 0,
+                name: format!("process-{}", pid),
+                cmdline: vec![],
+                state: ProcessState::Sleeping,
+                uid: 0,
+                threads: 1,
+                rss,
+                start_time: 1_700_000_000,
+                cpu_time,
+            }
+        }
+
+        pub fn child_process(pid: libc::pid_t, ppid: libc::pid_t) -> Process {
+            Process {
+                ppid,
+                ..process(pid, 0, 0)
+            }
+        }
+
+        pub fn processes(values: &[(libc::pid_t, u64, u64)]) -> Processes {
+            values
+                .iter()
+                .map(|(pid, rss, cpu_time)| (*pid, process(*pid, *rss, *cpu_time)))
+                .collect()
+        }
+    }
+}
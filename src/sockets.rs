@@ -0,0 +1,382 @@
+use super::Result;
+
+/// Socket usage of the system as reported in `/proc/net/sockstat` and `/proc/net/sockstat6`.
+/// The IPv6 counts are 0 when IPv6 is disabled.
+#[derive(Debug, PartialEq)]
+pub struct SocketStats {
+    /// Sockets in use of all protocols
+    pub used: u64,
+    pub tcp_in_use: u64,
+    /// TCP sockets no longer attached to a process
+    pub tcp_orphan: u64,
+    /// TCP sockets in the TIME_WAIT state
+    pub tcp_time_wait: u64,
+    /// TCP sockets allocated, including sockets that are not in use yet
+    pub tcp_allocated: u64,
+    /// Memory used by TCP sockets in KB
+    pub tcp_memory: u64,
+    pub udp_in_use: u64,
+    /// Memory used by UDP sockets in KB
+    pub udp_memory: u64,
+    pub raw_in_use: u64,
+    pub tcp6_in_use: u64,
+    pub udp6_in_use: u64,
+    pub raw6_in_use: u64,
+}
+
+/// Number of TCP connections per state from `/proc/net/tcp` and `/proc/net/tcp6`
+#[derive(Debug, PartialEq)]
+pub struct TcpStates {
+    pub established: u64,
+    pub syn_sent: u64,
+    pub syn_recv: u64,
+    pub fin_wait1: u64,
+    pub fin_wait2: u64,
+    pub time_wait: u64,
+    pub close: u64,
+    pub close_wait: u64,
+    pub last_ack: u64,
+    pub listen: u64,
+    pub closing: u64,
+    pub new_syn_recv: u64,
+}
+
+impl TcpStates {
+    /// Total number of TCP connections
+    pub fn total(&self) -> u64 {
+        self.established
+            + self.syn_sent
+            + self.syn_recv
+            + self.fin_wait1
+            + self.fin_wait2
+            + self.time_wait
+            + self.close
+            + self.close_wait
+            + self.last_ack
+            + self.listen
+            + self.closing
+            + self.new_syn_recv
+    }
+}
+
+/// Read the socket usage of the system.
+#[cfg(target_os = "linux")]
+pub fn read() -> Result<SocketStats> {
+    os::read()
+}
+
+/// Read the number of TCP connections per state for IPv4 and IPv6 combined.
+#[cfg(target_os = "linux")]
+pub fn read_tcp_states() -> Result<TcpStates> {
+    os::read_tcp_states()
+}
+
+#[cfg(target_os = "linux")]
+mod os {
+    use super::super::{file_to_buf_reader, parse_u64, path_to_string, ProbeError, Result};
+    use super::{SocketStats, TcpStates};
+    use std::collections::HashMap;
+    use std::io::{self, BufRead};
+    use std::path::Path;
+
+    #[inline]
+    pub fn read() -> Result<SocketStats> {
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as u64;
+        read_and_parse_sockstat(
+            Path::new("/proc/net/sockstat"),
+            Path::new("/proc/net/sockstat6"),
+            page_size,
+        )
+    }
+
+    #[inline]
+    pub fn read_tcp_states() -> Result<TcpStates> {
+        read_and_parse_tcp_states(&[Path::new("/proc/net/tcp"), Path::new("/proc/net/tcp6")])
+    }
+
+    pub fn read_and_parse_sockstat(
+        sockstat_path: &Path,
+        sockstat6_path: &Path,
+        page_size: u64,
+    ) -> Result<SocketStats> {
+        let fields = read_and_parse_sockstat_fields(sockstat_path)?;
+        // The file doesn't exist when IPv6 is disabled.
+        let fields6 = match read_and_parse_sockstat_fields(sockstat6_path) {
+            Err(ProbeError::IO(ref e, _)) if e.kind() == io::ErrorKind::NotFound => {
+                let mut fields6 = HashMap::new();
+                for name in &["TCP6 inuse", "UDP6 inuse", "RAW6 inuse"] {
+                    fields6.insert((*name).to_owned(), 0);
+                }
+                fields6
+            }
+            result => result?,
+        };
+
+        let field = |fields: &HashMap<String, u64>, name: &str| -> Result<u64> {
+            fields
+                .get(name)
+                .copied()
+                .ok_or_else(|| ProbeError::UnexpectedContent(format!("Field '{}' not found", name)))
+        };
+        // Memory is reported in pages, convert it to KB.
+        let pagesize = page_size / 1024;
+
+        Ok(SocketStats {
+            used: field(&fields, "sockets used")?,
+            tcp_in_use: field(&fields, "TCP inuse")?,
+            tcp_orphan: field(&fields, "TCP orphan")?,
+            tcp_time_wait: field(&fields, "TCP tw")?,
+            tcp_allocated: field(&fields, "TCP alloc")?,
+            tcp_memory: field(&fields, "TCP mem")? * pagesize,
+            udp_in_use: field(&fields, "UDP inuse")?,
+            udp_memory: field(&fields, "UDP mem")? * pagesize,
+            raw_in_use: field(&fields, "RAW inuse")?,
+            tcp6_in_use: field(&fields6, "TCP6 inuse")?,
+            udp6_in_use: field(&fields6, "UDP6 inuse")?,
+            raw6_in_use: field(&fields6, "RAW6 inuse")?,
+        })
+    }
+
+    // columns: protocol: name value name value ...
+    // Fields are returned as "protocol name", for example "TCP inuse".
+    fn read_and_parse_sockstat_fields(path: &Path) -> Result<HashMap<String, u64>> {
+        let reader = file_to_buf_reader(path)?;
+
+        let mut fields = HashMap::new();
+        for line_result in reader.lines() {
+            let line = line_result.map_err(|e| ProbeError::IO(e, path_to_string(path)))?;
+            let segments: Vec<&str> = line.split_whitespace().collect();
+            if segments.len() % 2 != 1 {
+                return Err(ProbeError::UnexpectedContent(format!(
+                    "Unexpected line '{}'",
+                    line
+                )));
+            }
+
+            let protocol = segments[0].trim_end_matches(':');
+            for pair in segments[1..].chunks(2) {
+                fields.insert(format!("{} {}", protocol, pair[0]), parse_u64(pair[1])?);
+            }
+        }
+
+        Ok(fields)
+    }
+
+    // columns: sl local_address rem_address st tx_queue:rx_queue ...
+    // The state is hexadecimal, `/proc/net/tcp6` doesn't exist when IPv6 is disabled.
+    pub fn read_and_parse_tcp_states(paths: &[&Path]) -> Result<TcpStates> {
+        let mut states = TcpStates {
+            established: 0,
+            syn_sent: 0,
+            syn_recv: 0,
+            fin_wait1: 0,
+            fin_wait2: 0,
+            time_wait: 0,
+            close: 0,
+            close_wait: 0,
+            last_ack: 0,
+            listen: 0,
+            closing: 0,
+            new_syn_recv: 0,
+        };
+
+        for path in paths {
+            let reader = match file_to_buf_reader(path) {
+                Ok(reader) => reader,
+                Err(ProbeError::IO(ref e, _)) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+
+            for line_result in reader.lines().skip(1) {
+                let line = line_result.map_err(|e| ProbeError::IO(e, path_to_string(path)))?;
+                let state = match line.split_whitespace().nth(3) {
+                    Some(state) => state,
+                    None => {
+                        return Err(ProbeError::UnexpectedContent(format!(
+                            "Unexpected line '{}'",
+                            line
+                        )))
+                    }
+                };
+
+                let count = match state {
+                    "01" => &mut states.established,
+                    "02" => &mut states.syn_sent,
+                    "03" => &mut states.syn_recv,
+                    "04" => &mut states.fin_wait1,
+                    "05" => &mut states.fin_wait2,
+                    "06" => &mut states.time_wait,
+                    "07" => &mut states.close,
+                    "08" => &mut states.close_wait,
+                    "09" => &mut states.last_ack,
+                    "0A" => &mut states.listen,
+                    "0B" => &mut states.closing,
+                    "0C" => &mut states.new_syn_recv,
+                    _ => {
+                        return Err(ProbeError::UnexpectedContent(format!(
+                            "Unknown TCP state '{}'",
+                            state
+                        )))
+                    }
+                };
+                *count += 1;
+            }
+        }
+
+        Ok(states)
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+    use super::super::ProbeError;
+    use super::os::{read_and_parse_sockstat, read_and_parse_tcp_states};
+    use super::{SocketStats, TcpStates};
+    use std::path::Path;
+
+    #[test]
+    fn test_read() {
+        assert!(super::read().is_ok());
+    }
+
+    #[test]
+    fn test_read_tcp_states() {
+        assert!(super::read_tcp_states().is_ok());
+    }
+
+    #[test]
+    fn test_read_and_parse_sockstat() {
+        let stats = read_and_parse_sockstat(
+            Path::new("fixtures/linux/sockets/proc_net_sockstat"),
+            Path::new("fixtures/linux/sockets/proc_net_sockstat6"),
+            4096,
+        )
+        .unwrap();
+
+        let expected = SocketStats {
+            used: 1342,
+            tcp_in_use: 87,
+            tcp_orphan: 2,
+            tcp_time_wait: 131,
+            tcp_allocated: 104,
+            tcp_memory: 76,
+            udp_in_use: 6,
+            udp_memory: 16,
+            raw_in_use: 1,
+            tcp6_in_use: 14,
+            udp6_in_use: 3,
+            raw6_in_use: 0,
+        };
+        assert_eq!(expected, stats);
+    }
+
+    #[test]
+    fn test_read_and_parse_sockstat_large_pages() {
+        let stats = read_and_parse_sockstat(
+            Path::new("fixtures/linux/sockets/proc_net_sockstat"),
+            Path::new("fixtures/linux/sockets/proc_net_sockstat6"),
+            65536,
+        )
+        .unwrap();
+
+        assert_eq!(1216, stats.tcp_memory);
+        assert_eq!(256, stats.udp_memory);
+    }
+
+    #[test]
+    fn test_read_and_parse_sockstat_without_ipv6() {
+        let stats = read_and_parse_sockstat(
+            Path::new("fixtures/linux/sockets/proc_net_sockstat"),
+            Path::new("/nonsense"),
+            4096,
+        )
+        .unwrap();
+
+        assert_eq!(87, stats.tcp_in_use);
+        assert_eq!(0, stats.tcp6_in_use);
+    }
+
+    #[test]
+    fn test_read_and_parse_sockstat_wrong_path() {
+        match read_and_parse_sockstat(
+            Path::new("/nonsense"),
+            Path::new("fixtures/linux/sockets/proc_net_sockstat6"),
+            4096,
+        ) {
+            Err(ProbeError::IO(_, _)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_sockstat_incomplete() {
+        match read_and_parse_sockstat(
+            Path::new("fixtures/linux/sockets/proc_net_sockstat_incomplete"),
+            Path::new("fixtures/linux/sockets/proc_net_sockstat6"),
+            4096,
+        ) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_sockstat_garbage() {
+        match read_and_parse_sockstat(
+            Path::new("fixtures/linux/sockets/proc_net_sockstat_garbage"),
+            Path::new("fixtures/linux/sockets/proc_net_sockstat6"),
+            4096,
+        ) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_tcp_states() {
+        let states = read_and_parse_tcp_states(&[
+            Path::new("fixtures/linux/sockets/proc_net_tcp"),
+            Path::new("fixtures/linux/sockets/proc_net_tcp6"),
+        ])
+        .unwrap();
+
+        let expected = TcpStates {
+            established: 3,
+            syn_sent: 0,
+            syn_recv: 0,
+            fin_wait1: 0,
+            fin_wait2: 1,
+            time_wait: 2,
+            close: 0,
+            close_wait: 2,
+            last_ack: 0,
+            listen: 3,
+            closing: 0,
+            new_syn_recv: 0,
+        };
+        assert_eq!(expected, states);
+        assert_eq!(11, states.total());
+    }
+
+    #[test]
+    fn test_read_and_parse_tcp_states_without_ipv6() {
+        let states = read_and_parse_tcp_states(&[
+            Path::new("fixtures/linux/sockets/proc_net_tcp"),
+            Path::new("/nonsense"),
+        ])
+        .unwrap();
+
+        assert_eq!(2, states.listen);
+        assert_eq!(8, states.total());
+    }
+
+    #[test]
+    fn test_read_and_parse_tcp_states_garbage() {
+        let path = Path::new("fixtures/linux/sockets/proc_net_tcp_garbage");
+        match read_and_parse_tcp_states(&[path]) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+}
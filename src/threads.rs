@@ -0,0 +1,379 @@
+use super::processes::ProcessState;
+use super::{calculate_time_difference, time_adjusted, Result};
+use std::collections::HashMap;
+
+/// A thread of a process as reported in `/proc/<pid>/task/<tid>`
+#[derive(Debug, PartialEq)]
+pub struct Thread {
+    pub tid: libc::pid_t,
+    pub name: String,
+    pub state: ProcessState,
+    /// Time spent in user mode in nanoseconds
+    pub utime: u64,
+    /// Time spent in kernel mode in nanoseconds
+    pub stime: u64,
+    /// Start time in nanoseconds since boot
+    pub start_time: u64,
+    pub voluntary_ctxt_switches: u64,
+    pub nonvoluntary_ctxt_switches: u64,
+}
+
+pub type Threads = HashMap<libc::pid_t, Thread>;
+
+/// Measurement of the threads of a process at a certain time
+#[derive(Debug, PartialEq)]
+pub struct ThreadsMeasurement {
+    pub precise_time_ns: u64,
+    pub threads: Threads,
+}
+
+impl ThreadsMeasurement {
+    /// Calculate the CPU time and context switches per minute of every thread based on this
+    /// measurement and a measurement in the future, sorted by CPU time, highest first. Threads
+    /// that are not present in both measurements are skipped. It is advisable to make the next
+    /// measurement roughly a minute from this one for the most reliable result.
+    pub fn calculate_per_minute(
+        &self,
+        next_measurement: &ThreadsMeasurement,
+    ) -> Result<Vec<ThreadPerMinute>> {
+        let time_difference =
+            calculate_time_difference(self.precise_time_ns, next_measurement.precise_time_ns)?;
+
+        let mut threads = Vec::new();
+        for (tid, thread) in self.threads.iter() {
+            let next_thread = match next_measurement.threads.get(tid) {
+                // The tid was reused by another thread when the start time differs
+                Some(next_thread) if next_thread.start_time == thread.start_time => next_thread,
+                _ => continue,
+            };
+
+            threads.push(ThreadPerMinute {
+                tid: *tid,
+                name: next_thread.name.to_owned(),
+                utime: time_adjusted("utime", next_thread.utime, thread.utime, time_difference)?,
+                stime: time_adjusted("stime", next_thread.stime, thread.stime, time_difference)?,
+                voluntary_ctxt_switches: time_adjusted(
+                    "voluntary_ctxt_switches",
+                    next_thread.voluntary_ctxt_switches,
+                    thread.voluntary_ctxt_switches,
+                    time_difference,
+                )?,
+                nonvoluntary_ctxt_switches: time_adjusted(
+                    "nonvoluntary_ctxt_switches",
+                    next_thread.nonvoluntary_ctxt_switches,
+                    thread.nonvoluntary_ctxt_switches,
+                    time_difference,
+                )?,
+            });
+        }
+
+        threads.sort_by(|a, b| {
+            (b.utime + b.stime)
+                .cmp(&(a.utime + a.stime))
+                .then(a.tid.cmp(&b.tid))
+        });
+        Ok(threads)
+    }
+}
+
+/// CPU time and context switches of a thread for a minute
+#[derive(Debug, PartialEq)]
+pub struct ThreadPerMinute {
+    pub tid: libc::pid_t,
+    pub name: String,
+    /// Time spent in user mode in nanoseconds
+    pub utime: u64,
+    /// Time spent in kernel mode in nanoseconds
+    pub stime: u64,
+    pub voluntary_ctxt_switches: u64,
+    pub nonvoluntary_ctxt_switches: u64,
+}
+
+impl ThreadPerMinute {
+    /// CPU time in user and kernel mode in percentages of a single CPU
+    pub fn in_percentage(&self) -> f32 {
+        // 60_000_000_000 being the total value. This is 60 seconds expressed in nanoseconds.
+        ((self.utime + self.stime) as f32 / 60_000_000_000.0) * 100.0
+    }
+}
+
+/// Read the threads of this process.
+#[cfg(target_os = "linux")]
+pub fn read_self() -> Result<ThreadsMeasurement> {
+    os::read_self()
+}
+
+/// Read the threads of a process with given pid. Threads that exit while reading are skipped.
+#[cfg(target_os = "linux")]
+pub fn read_of(pid: libc::pid_t) -> Result<ThreadsMeasurement> {
+    os::read_of(pid)
+}
+
+#[cfg(target_os = "linux")]
+mod os {
+    use super::super::processes::os::{
+        is_exited, parse_state, read_and_parse_ctxt_switches, split_proc_pid_stat,
+    };
+    use super::super::{
+        file_to_string, parse_u64, path_to_string, precise_time_ns, ticks_to_ns, ProbeError, Result,
+    };
+    use super::{Thread, ThreadsMeasurement};
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::Path;
+
+    #[inline]
+    pub fn read_self() -> Result<ThreadsMeasurement> {
+        read_and_parse_threads(Path::new("/proc/self/task"))
+    }
+
+    #[inline]
+    pub fn read_of(pid: libc::pid_t) -> Result<ThreadsMeasurement> {
+        read_and_parse_threads(Path::new(&format!("/proc/{}/task", pid)))
+    }
+
+    pub fn read_and_parse_threads(task_dir: &Path) -> Result<ThreadsMeasurement> {
+        let precise_time_ns = precise_time_ns();
+        let entries =
+            fs::read_dir(task_dir).map_err(|e| ProbeError::IO(e, path_to_string(task_dir)))?;
+
+        let mut threads = HashMap::new();
+        for entry in entries {
+            let entry = entry.map_err(|e| ProbeError::IO(e, path_to_string(task_dir)))?;
+            let tid: libc::pid_t = match entry.file_name().to_str().and_then(|n| n.parse().ok()) {
+                Some(tid) => tid,
+                None => continue,
+            };
+
+            match read_and_parse_thread(&entry.path(), tid) {
+                Ok(thread) => {
+                    threads.insert(tid, thread);
+                }
+                Err(ProbeError::IO(ref e, _)) if is_exited(e) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(ThreadsMeasurement {
+            precise_time_ns,
+            threads,
+        })
+    }
+
+    pub fn read_and_parse_thread(thread_dir: &Path, tid: libc::pid_t) -> Result<Thread> {
+        let stat = file_to_string(&thread_dir.join("stat"))?;
+        let (name, fields) = split_proc_pid_stat(&stat)?;
+        if fields.len() < 20 {
+            return Err(ProbeError::UnexpectedContent(
+                "Incorrect number of segments".to_owned(),
+            ));
+        }
+
+        let (voluntary_ctxt_switches, nonvoluntary_ctxt_switches) =
+            read_and_parse_ctxt_switches(&thread_dir.join("status"))?;
+
+        // Times are in clock ticks, convert them to nanoseconds.
+        Ok(Thread {
+            tid,
+            name: name.to_owned(),
+            state: parse_state(fields[0])?,
+            utime: ticks_to_ns(parse_u64(fields[11])?),
+            stime: ticks_to_ns(parse_u64(fields[12])?),
+            start_time: ticks_to_ns(parse_u64(fields[19])?),
+            voluntary_ctxt_switches,
+            nonvoluntary_ctxt_switches,
+        })
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+    use super::super::processes::ProcessState;
+    use super::super::ProbeError;
+    use super::os::read_and_parse_threads;
+    use super::{Thread, ThreadsMeasurement};
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    #[test]
+    fn test_read_self() {
+        let measurement = super::read_self().unwrap();
+        let pid = unsafe { libc::getpid() };
+        // The main thread has the same id as the process
+        assert!(measurement.threads.contains_key(&pid));
+    }
+
+    #[test]
+    fn test_read_of() {
+        let pid = unsafe { libc::getpid() };
+        assert!(super::read_of(pid).is_ok());
+    }
+
+    #[test]
+    fn test_read_of_invalid_pid() {
+        assert!(super::read_of(0).is_err());
+    }
+
+    #[test]
+    fn test_read_and_parse_threads() {
+        let path = Path::new("fixtures/linux/threads/task");
+        let measurement = read_and_parse_threads(path).unwrap();
+
+        // The thread without a stat file exited while reading and is skipped
+        assert_eq!(2, measurement.threads.len());
+        assert_eq!(
+            &Thread {
+                tid: 2741,
+                name: "ruby".to_owned(),
+                state: ProcessState::Sleeping,
+                utime: 2_210_000_000,
+                stime: 340_000_000,
+                start_time: 812_340_000_000,
+                voluntary_ctxt_switches: 1290,
+                nonvoluntary_ctxt_switches: 37,
+            },
+            measurement.threads.get(&2741).unwrap()
+        );
+
+        let worker = measurement.threads.get(&2745).unwrap();
+        assert_eq!("worker (1)", worker.name);
+        assert_eq!(ProcessState::Running, worker.state);
+        assert_eq!(13_220_000_000, worker.utime);
+        assert_eq!(9121, worker.nonvoluntary_ctxt_switches);
+    }
+
+    #[test]
+    fn test_read_and_parse_threads_wrong_path() {
+        match read_and_parse_threads(Path::new("/nonsense")) {
+            Err(ProbeError::IO(_, _)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_threads_incomplete() {
+        let path = Path::new("fixtures/linux/threads/task_incomplete");
+        match read_and_parse_threads(path) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_read_and_parse_threads_garbage() {
+        let path = Path::new("fixtures/linux/threads/task_garbage");
+        match read_and_parse_threads(path) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_calculate_per_minute() {
+        let measurement1 = ThreadsMeasurement {
+            precise_time_ns: 60_000_000_000,
+            threads: helpers::threads(&[(1, 1_000_000_000, 100), (2, 1_000_000_000, 100)]),
+        };
+        let mut threads =
+            helpers::threads(&[(1, 1_500_000_000, 400), (2, 7_000_000_000, 100), (3, 0, 0)]);
+        // Tid 1 is not reused, tid 2 is
+        threads.get_mut(&2).unwrap().start_time = 2_000_000_000;
+        let measurement2 = ThreadsMeasurement {
+            precise_time_ns: 90_000_000_000,
+            threads,
+        };
+
+        let per_minute = measurement1.calculate_per_minute(&measurement2).unwrap();
+        assert_eq!(1, per_minute.len());
+        assert_eq!(1, per_minute[0].tid);
+        assert_eq!(500_000_000, per_minute[0].utime);
+        assert_eq!(500_000_000, per_minute[0].stime);
+        assert_eq!(600, per_minute[0].voluntary_ctxt_switches);
+        assert_eq!(600, per_minute[0].nonvoluntary_ctxt_switches);
+        assert!(per_minute[0].in_percentage() > 1.6);
+        assert!(per_minute[0].in_percentage() <= 1.7);
+    }
+
+    #[test]
+    fn test_calculate_per_minute_sorted_by_cpu() {
+        let measurement1 = ThreadsMeasurement {
+            precise_time_ns: 60_000_000_000,
+            threads: helpers::threads(&[(1, 0, 0), (2, 0, 0), (3, 0, 0)]),
+        };
+        let measurement2 = ThreadsMeasurement {
+            precise_time_ns: 120_000_000_000,
+            threads: helpers::threads(&[
+                (1, 1_000_000_000, 0),
+                (2, 3_000_000_000, 0),
+                (3, 2_000_000_000, 0),
+            ]),
+        };
+
+        let tids: Vec<libc::pid_t> = measurement1
+            .calculate_per_minute(&measurement2)
+            .unwrap()
+            .iter()
+            .map(|thread| thread.tid)
+            .collect();
+        assert_eq!(vec![2, 3, 1], tids);
+    }
+
+    #[test]
+    fn test_calculate_per_minute_wrong_times() {
+        let measurement1 = ThreadsMeasurement {
+            precise_time_ns: 90_000_000_000,
+            threads: HashMap::new(),
+        };
+        let measurement2 = ThreadsMeasurement {
+            precise_time_ns: 60_000_000_000,
+            threads: HashMap::new(),
+        };
+
+        match measurement1.calculate_per_minute(&measurement2) {
+            Err(ProbeError::InvalidInput(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_calculate_per_minute_values_lower() {
+        let measurement1 = ThreadsMeasurement {
+            precise_time_ns: 60_000_000_000,
+            threads: helpers::threads(&[(1, 1_000_000_000, 0)]),
+        };
+        let measurement2 = ThreadsMeasurement {
+            precise_time_ns: 90_000_000_000,
+            threads: helpers::threads(&[(1, 500_000_000, 0)]),
+        };
+
+        match measurement1.calculate_per_minute(&measurement2) {
+            Err(ProbeError::UnexpectedContent(_)) => (),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    mod helpers {
+        use super::super::super::processes::ProcessState;
+        use super::super::{Thread, Threads};
+
+        pub fn threads(values: &[(libc::pid_t, u64, u64)]) -> Threads {
+            values
+                .iter()
+                .map(|(tid, cpu_time, ctxt_switches)| {
+                    let thread = Thread {
+                        tid: *tid,
+                        name: format!("thread-{}", tid),
+                        state: ProcessState::Sleeping,
+                        utime: *cpu_time / 2,
+                        stime: *cpu_time / 2,
+                        start_time: 1_000_000_000,
+                        voluntary_ctxt_switches: *ctxt_switches,
+                        nonvoluntary_ctxt_switches: *ctxt_switches,
+                    };
+                    (*tid, thread)
+                })
+                .collect()
+        }
+    }
+}